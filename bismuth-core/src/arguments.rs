@@ -14,7 +14,32 @@ pub enum Commands {
         /// Do not confirm to destory the bulid directory
         #[arg(short, long)]
         noconfirm: bool,
+        /// Include pages marked as drafts
+        #[arg(long)]
+        drafts: bool,
+        /// Include pages with a publish date in the future
+        #[arg(long)]
+        future: bool,
+        /// The dir to write the site to, overrides `out` in bismuth.toml
+        #[arg(long)]
+        out: Option<String>,
+        /// The dir to load the markdown files from, overrides `src` in bismuth.toml
+        #[arg(long)]
+        src: Option<String>,
+        /// Add a hash of the content to the names of the assets, overrides `fingerprint` in bismuth.toml
+        #[arg(long)]
+        fingerprint: bool,
+        /// Minify the html and css, overrides `minify` in bismuth.toml
+        #[arg(long)]
+        minify: bool,
+        /// Fail the build if there are broken links, overrides `strict_links` in bismuth.toml
+        #[arg(long)]
+        strict_links: bool,
     },
+    /// Builds the site to a temporary dir, and checks every link, `#id` and image in the markdown
+    Check,
+    /// Builds every code base state used by a snippet, to check that they still work
+    CheckSnippets,
     /// Inits a new project
     Init {
         /// Name of the project to create
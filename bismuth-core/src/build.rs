@@ -1,15 +1,53 @@
+use chrono::{DateTime, Local};
+
+use bismuth_custom::config::CustomConfig;
+use bismuth_error::warning::{Warning, Warnings};
 use bismuth_html::{
-    render_list,
-    write::{move_css_folder, utils::write_css},
+    fingerprint::{fingerprint, MANIFEST},
+    highlight::{self, Highlighter},
+    image::ImagePipeline,
+    links::{self, BrokenLink},
+    minify::{minify_dir, Saving},
+    redirect::{self, Alias},
+    render_list, search,
+    seo::Seo,
+    write::{copy_static, move_css_folder, utils::write_css, Dirs},
+    RenderConfig,
 };
 use bismuth_lexer::Lexer;
 use bismuth_md::MarkdownFile;
-use bismuth_parser::Parser;
+use bismuth_parser::{
+    url::{self, Urls},
+    Parser,
+};
 use bismuth_tui::prompt::{builtin::YesNo, Input};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::path::Path;
 
 use crate::config::Config;
 
+#[derive(Debug, Default)]
+pub struct BuildOptions {
+    /// Do not confirm to destory the bulid directory
+    pub noconfirm: bool,
+    /// Build pages marked as drafts
+    pub drafts: bool,
+    /// Build pages with a publish date in the future
+    pub future: bool,
+    /// Overrides the `out` dir in the config
+    pub out: Option<String>,
+    /// Overrides the `src` dir in the config
+    pub src: Option<String>,
+    /// Fingerprint the assets, even if it is not enabled in the config
+    pub fingerprint: bool,
+    /// Minify the output, even if it is not enabled in the config
+    pub minify: bool,
+    /// Fail on broken links, even if it is not enabled in the config
+    pub strict_links: bool,
+    /// Only check the links, the output is removed after
+    pub check: bool,
+}
+
 pub fn run_lexer(files: Vec<MarkdownFile>) -> Vec<Lexer> {
     files
         .iter()
@@ -22,14 +60,96 @@ pub fn run_lexer(files: Vec<MarkdownFile>) -> Vec<Lexer> {
 }
 
 pub fn run_parser(files: Vec<Lexer>) -> Vec<Parser> {
-    let mut parsed_files = files
+    files
         .iter()
         .map(|lexer| {
             let mut parser = Parser::new(lexer.clone());
             parser.parse().unwrap();
-            Some(parser)
+            parser
+        })
+        .collect::<Vec<Parser>>()
+}
+
+/// Sets when each file was last modified, this is used when a page is missing its date
+pub fn load_modified(files: &mut [Parser], src: &Path) {
+    for file in files {
+        let path = src.join(
+            file.metadata
+                .absolute_path
+                .to_string_lossy()
+                .trim_start_matches(['/', '\\']),
+        );
+        file.metadata.modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|m| DateTime::<Local>::from(m).naive_local());
+    }
+}
+
+/// Removes drafts, scheduled and expired pages
+/// This has to be done before running the customs, so they do not show up in other pages
+pub fn filter_published(files: Vec<Parser>, options: &BuildOptions) -> Vec<Parser> {
+    let now = Local::now().naive_local();
+    files
+        .into_iter()
+        .filter(|file| {
+            file.metadata
+                .frontmatter
+                .is_published(&now, options.drafts, options.future)
         })
-        .collect::<Vec<Option<Parser>>>();
+        .collect::<Vec<Parser>>()
+}
+
+/// Reports the pages that the permalink can not be made for, and the pages that have the same url
+/// Aliases can not have the url of a page or of another alias
+/// These are errors, so they stop the build
+pub fn check_urls(files: &[Parser], aliases: &[Alias], urls: &Urls, warnings: &Warnings) {
+    let mut taken: HashMap<String, &Path> = HashMap::new();
+    for file in files {
+        let path = file.metadata.absolute_path.clone();
+        if let Err(e) = urls.check(file) {
+            warnings.error(Warning::new(path, Some("permalinks"), e));
+            continue;
+        }
+        let page = urls.path(file);
+        match taken.get(&url::html_file(&page)) {
+            Some(other) => warnings.error(Warning::new(
+                path,
+                Some("permalinks"),
+                format!("`{page}` is also the url of {}", other.to_string_lossy()),
+            )),
+            None => {
+                taken.insert(url::html_file(&page), &file.metadata.absolute_path);
+            }
+        }
+    }
+    for alias in aliases {
+        match taken.get(&url::html_file(&alias.from)) {
+            Some(other) => warnings.error(Warning::new(
+                alias.file.clone(),
+                Some("aliases"),
+                format!(
+                    "the alias `{}` is also the url of {}",
+                    alias.from,
+                    other.to_string_lossy()
+                ),
+            )),
+            None => {
+                taken.insert(url::html_file(&alias.from), &alias.file);
+            }
+        }
+    }
+}
+
+pub fn run_customs(
+    mut files: Vec<Parser>,
+    config: &CustomConfig,
+    warnings: &Warnings,
+) -> Vec<Parser> {
+    for file in &mut files {
+        bismuth_custom::expand_includes(file, config, warnings);
+    }
+    let mut parsed_files = files.into_iter().map(Some).collect::<Vec<Option<Parser>>>();
 
     let mut index = 0;
     while index < parsed_files.len() {
@@ -40,6 +160,8 @@ pub fn run_parser(files: Vec<Lexer>) -> Vec<Parser> {
                 .iter()
                 .map(|e| e.as_ref())
                 .collect::<Vec<Option<&Parser>>>(),
+            config,
+            warnings,
         );
         parsed_files[index] = Some(parsered);
         index += 1;
@@ -50,48 +172,250 @@ pub fn run_parser(files: Vec<Lexer>) -> Vec<Parser> {
         .collect::<Vec<Parser>>()
 }
 
-pub fn build(dir: String, noconfirm: bool) {
-    let path = Path::new(&dir).canonicalize().unwrap();
-
-    let config = Config::new(&path);
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        _ => format!("{:.1} KB", bytes as f64 / 1024.0),
+    }
+}
 
-    let mut src_path = path.clone();
-    src_path.push("src/");
+pub fn print_savings(savings: &[Saving]) {
+    for saving in savings {
+        println!(
+            "  {}: {} -> {} (-{:.0}%)",
+            saving.path.to_string_lossy().replace('\\', "/"),
+            format_size(saving.before),
+            format_size(saving.after),
+            saving.percent()
+        );
+    }
+    let total = Saving {
+        path: Default::default(),
+        before: savings.iter().map(|s| s.before).sum(),
+        after: savings.iter().map(|s| s.after).sum(),
+    };
+    println!(
+        "Minified {} files, saved {} (-{:.0}%)",
+        savings.len(),
+        format_size(total.saved()),
+        total.percent()
+    );
+}
 
+/// Loads, lexes and parses all the markdown files in `src`
+pub fn load_pages(src: &Path) -> Vec<Parser> {
     println!("Loading files...");
     let md_files =
-        bismuth_md::load::load_from_dir(&src_path, &src_path.clone().canonicalize().unwrap())
-            .unwrap();
+        bismuth_md::load::load_from_dir(src, &src.to_path_buf().canonicalize().unwrap()).unwrap();
     println!("Parsing files...");
     let tokenized_file = run_lexer(md_files);
-    let parsers = run_parser(tokenized_file);
+    let mut parsers = run_parser(tokenized_file);
+    load_modified(&mut parsers, src);
+    parsers
+}
+
+/// Reports the broken links at their place in the markdown
+/// These are errors if `strict`, so they stop the build
+pub fn report_broken(broken: &[BrokenLink], strict: bool, warnings: &Warnings) {
+    for link in broken {
+        let warning = Warning::new(
+            link.reference.file.clone(),
+            Some("links"),
+            link.reason.clone(),
+        )
+        .with_line(link.reference.line);
+        match strict {
+            true => warnings.error(warning),
+            false => warnings.warn(warning),
+        }
+    }
+}
+
+/// Builds the site to a temporary dir, and checks the links in it
+/// The dir is removed when it is done, even if the build fails
+pub fn check(dir: String) {
+    let out = match tempfile::Builder::new().prefix("bismuth-check-").tempdir() {
+        Ok(out) => out,
+        Err(e) => exit(format!("Could not make a temporary dir: {e}")),
+    };
+    let result = run(
+        dir,
+        BuildOptions {
+            noconfirm: true,
+            out: Some(out.path().to_string_lossy().to_string()),
+            strict_links: true,
+            check: true,
+            ..Default::default()
+        },
+    );
+    drop(out);
+    if let Err(e) = result {
+        exit(e);
+    }
+}
+
+fn exit(message: String) -> ! {
+    println!("{message}");
+    std::process::exit(1);
+}
+
+pub fn build(dir: String, options: BuildOptions) {
+    if let Err(e) = run(dir, options) {
+        exit(e);
+    }
+}
+
+/// Builds the site, the error is the reason the build stopped
+fn run(dir: String, options: BuildOptions) -> Result<(), String> {
+    let path = Path::new(&dir).canonicalize().unwrap();
+
+    let mut config = Config::new(&path);
+    // so the dirs and the customs use the same src and out
+    if let Some(src) = options.src.clone() {
+        config.build.src = src;
+    }
+    if let Some(out) = options.out.clone() {
+        config.build.out = out;
+    }
+    let warnings = Warnings::default();
+
+    let dirs = Dirs::new(
+        &path,
+        &config.build.src,
+        &config.build.out,
+        &config.build.assets,
+        &config.build.static_files,
+    );
+    dirs.check()?;
+    let parsers = load_pages(&dirs.src);
+
+    let total = parsers.len();
+    let mut parsers = filter_published(parsers, &options);
+    if parsers.len() != total {
+        println!(
+            "Skipped {} draft, scheduled or expired pages",
+            total - parsers.len()
+        );
+    }
+    parsers.iter_mut().for_each(|p| config.urls.rebase(p));
+
+    let generated = bismuth_custom::gen_pages(
+        &parsers.iter().collect::<Vec<&Parser>>(),
+        &config.urls,
+        &warnings,
+    );
+    parsers.append(&mut run_parser(run_lexer(generated)));
+    let aliases = redirect::aliases(&parsers, &config.urls);
+    check_urls(&parsers, &aliases, &config.urls, &warnings);
+
+    let parsers = run_customs(parsers, &config.custom_config(), &warnings);
+    let errors = warnings.print();
+    if errors > 0 {
+        return Err(format!("Build failed with {errors} errors"));
+    }
     println!("---");
 
-    if PathBuf::from("./build/").exists() && !noconfirm {
+    if dirs.out.exists() && !options.noconfirm {
+        let out = dirs.out.to_string_lossy();
         let mut check_remove_dir = YesNo::new(
-            String::from("Warning! ./build/ dir will be removed! Would you like to proceed (Y/n):"),
-            String::from("Warning! All the contents in the ./build dir will be removed"),
+            format!("Warning! {out} dir will be removed! Would you like to proceed (Y/n):"),
+            format!("Warning! All the contents in the {out} dir will be removed"),
             None,
         );
         check_remove_dir.run();
         if let Some(true) = check_remove_dir.result {
             println!("Run with `--noconfirm` to auto accept this message");
             println!("Removing dir...");
-            std::fs::remove_dir_all(PathBuf::from("./build/")).unwrap();
+            std::fs::remove_dir_all(&dirs.out).unwrap();
         } else {
-            println!("Exiting...");
-            std::process::exit(1);
+            return Err(String::from("Exiting..."));
         }
     }
 
+    println!("Copying static files...");
+    let copied = copy_static(&dirs, &config.build.ignore).unwrap();
+    println!("Copied {copied} files");
+
     println!("Rendering...");
-    let renderers = render_list(parsers);
+    let highlighter =
+        Highlighter::new(&config.highlight, &dirs.project).map_err(|e| e.to_string())?;
+    let render_config = RenderConfig {
+        urls: config.urls.clone(),
+        seo: Seo::new(config.seo.clone(), &config.name),
+        images: Some(ImagePipeline::new(config.images.clone(), &dirs)),
+        highlighter,
+    };
+    let references = links::references(&parsers, &config.urls);
+    let documents = config
+        .search
+        .enabled
+        .then(|| search::documents(&parsers, &config.urls));
+    let renderers = render_list(parsers, &render_config);
+    if let Some(images) = &render_config.images {
+        for warning in images.warnings.take_warnings() {
+            warnings.warn(warning);
+        }
+    }
     println!("Writing files...");
     for r in renderers {
-        r.write().unwrap();
+        if let Err(e) = r.write(&dirs) {
+            warnings.error(Warning::new(
+                r.parser.metadata.absolute_path.clone(),
+                None,
+                format!("could not be written: {e}"),
+            ));
+        }
+    }
+    let errors = warnings.print();
+    if errors > 0 {
+        return Err(format!("Build failed with {errors} errors"));
+    }
+    redirect::write(&aliases, &config.redirects, &render_config, &dirs.out).unwrap();
+    write_css(&config.gen_colors(), "colors", &dirs.out).unwrap();
+    // only class mode needs a stylesheet, the pages only link it then
+    if let Some(css) = render_config
+        .highlighter
+        .stylesheet(config.theme.is_dark())
+        .map_err(|e| e.to_string())?
+    {
+        write_css(&css, highlight::STYLESHEET, &dirs.out).unwrap();
+    }
+    move_css_folder(&dirs.out).unwrap();
+    if let Some(documents) = &documents {
+        search::write_index(documents, &config.search, &dirs.out).unwrap();
+    }
+
+    println!("Checking links...");
+    let broken = links::check(&references, &config.urls, &dirs.out).unwrap();
+    report_broken(
+        &broken,
+        options.strict_links || config.build.strict_links,
+        &warnings,
+    );
+    let errors = warnings.print();
+    if options.check {
+        if !broken.is_empty() {
+            return Err(format!("{} broken links", broken.len()));
+        }
+        println!("Checked {} links, none are broken", references.len());
+        return Ok(());
+    }
+    if errors > 0 {
+        return Err(format!("Build failed with {errors} broken links"));
+    }
+
+    // before fingerprinting, so the hashes are of the minified files
+    if options.minify || config.build.minify {
+        println!("Minifying...");
+        print_savings(&minify_dir(&dirs.out).unwrap());
+    }
+
+    if options.fingerprint || config.build.fingerprint {
+        println!("Fingerprinting assets...");
+        let manifest = fingerprint(&dirs, &config.urls).unwrap();
+        println!("Fingerprinted {} assets, see {MANIFEST}", manifest.len());
     }
-    write_css(&config.gen_colors(), "colors").unwrap();
-    move_css_folder().unwrap();
 
     println!("Site built!");
+    Ok(())
 }
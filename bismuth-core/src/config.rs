@@ -2,6 +2,12 @@ use serde::Deserialize;
 use std::{fs, path::Path};
 use toml;
 
+use bismuth_custom::config::{CustomConfig, FooterConfig, SnippetsConfig};
+use bismuth_html::{
+    highlight::HighlightConfig, image::ImageConfig, redirect::RedirectConfig, search::SearchConfig,
+    seo::SeoConfig,
+};
+use bismuth_parser::url::{PermalinkConfig, UrlMode, Urls};
 use bismuth_tui::prompt::{builtin::YesNo, Input};
 
 pub const CONFIG_FILE: &str = include_str!("../config.toml");
@@ -15,7 +21,7 @@ pub fn make_config(dir: &Path) -> Result<(), std::io::Error> {
     let full_dir = dir.canonicalize().unwrap();
     let name = full_dir
         .components()
-        .last()
+        .next_back()
         .expect("Should have last")
         .as_os_str()
         .to_str()
@@ -31,6 +37,11 @@ pub fn make_config(dir: &Path) -> Result<(), std::io::Error> {
 pub struct WebsiteConfig {
     name: String,
     std: bool,
+    /// Where the site is hosted, ie. `https://example.com/docs/` or `/docs/`
+    #[serde(default)]
+    base_url: String,
+    #[serde(default)]
+    urls: UrlMode,
 }
 
 #[allow(dead_code)]
@@ -57,6 +68,24 @@ impl Theme {
             link_hover: Some(self.link_hover.unwrap_or(String::from("#d65d0e"))),
         }
     }
+
+    /// If the main background is dark, colors that can not be read default to dark
+    pub fn is_dark(&self) -> bool {
+        let hex = self
+            .background_1
+            .as_deref()
+            .unwrap_or_default()
+            .trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .map(f64::from)
+        };
+        match (channel(0), channel(2), channel(4)) {
+            (Some(r), Some(g), Some(b)) => 0.299 * r + 0.587 * g + 0.114 * b < 128.0,
+            _ => true,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -66,12 +95,56 @@ pub struct Addons {
     plugins: Option<String>,
 }
 
+/// The `[build]` section, all the dirs are relative to the project dir
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(default)]
+pub struct BuildConfig {
+    pub src: String,
+    pub out: String,
+    pub assets: String,
+    /// Copied to the root of the site as is
+    #[serde(rename = "static")]
+    pub static_files: String,
+    /// Glob patterns for files that should not be copied from the assets, static and src dirs
+    pub ignore: Vec<String>,
+    /// Adds a hash of the content to the names of the assets, ie. `base.3f2a1c9e.css`
+    pub fingerprint: bool,
+    /// Minifies the html and css, including inline css and js
+    pub minify: bool,
+    /// Fails the build if a link, `#id` or image in the markdown is not in the built site
+    pub strict_links: bool,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            src: String::from("src"),
+            out: String::from("build"),
+            assets: String::from("assets"),
+            static_files: String::from("static"),
+            ignore: vec![String::from(".DS_Store"), String::from("Thumbs.db")],
+            fingerprint: false,
+            minify: false,
+            strict_links: false,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Default, Debug, PartialEq)]
 pub struct TomlConfig {
     website: WebsiteConfig,
     theme: Option<Theme>,
     addons: Option<Addons>,
+    footer: Option<FooterConfig>,
+    build: Option<BuildConfig>,
+    images: Option<ImageConfig>,
+    highlight: Option<HighlightConfig>,
+    snippets: Option<SnippetsConfig>,
+    search: Option<SearchConfig>,
+    seo: Option<SeoConfig>,
+    permalinks: Option<PermalinkConfig>,
+    redirects: Option<RedirectConfig>,
 }
 
 #[derive(Debug)]
@@ -81,6 +154,15 @@ pub struct Config<'a> {
     pub addons: Addons,
     pub directory: &'a Path,
     pub bstd: bool,
+    pub footer: FooterConfig,
+    pub build: BuildConfig,
+    pub images: ImageConfig,
+    pub highlight: HighlightConfig,
+    pub snippets: SnippetsConfig,
+    pub search: SearchConfig,
+    pub seo: SeoConfig,
+    pub urls: Urls,
+    pub redirects: RedirectConfig,
 }
 
 macro_rules! replace_css {
@@ -113,12 +195,36 @@ impl<'a> Config<'a> {
         let content = fs::read_to_string(dir.join("bismuth.toml")).unwrap();
         let toml_config = Self::new_toml_config(&content);
 
+        let urls = Urls::new(&toml_config.website.base_url, toml_config.website.urls)
+            .with_permalinks(toml_config.permalinks.unwrap_or_default());
         Config {
             directory: dir,
             name: toml_config.website.name,
             addons: toml_config.addons.unwrap_or_default(),
             theme: toml_config.theme.unwrap_or_default(),
             bstd: toml_config.website.std,
+            footer: toml_config.footer.unwrap_or_default(),
+            build: toml_config.build.unwrap_or_default(),
+            images: toml_config.images.unwrap_or_default(),
+            highlight: toml_config.highlight.unwrap_or_default(),
+            snippets: toml_config.snippets.unwrap_or_default(),
+            search: toml_config.search.unwrap_or_default(),
+            seo: toml_config.seo.unwrap_or_default(),
+            urls,
+            redirects: toml_config.redirects.unwrap_or_default(),
+        }
+    }
+
+    /// The config that is passed to the customs
+    pub fn custom_config(&self) -> CustomConfig {
+        CustomConfig {
+            name: self.name.clone(),
+            project: self.directory.to_path_buf(),
+            src: self.directory.join(&self.build.src),
+            footer: self.footer.clone(),
+            snippets: self.snippets.clone(),
+            search_index: self.search.file.clone(),
+            urls: self.urls.clone(),
         }
     }
 
@@ -151,6 +257,9 @@ impl<'a> Config<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bismuth_custom::config::FooterLink;
+    use bismuth_html::highlight::HighlightMode;
+    use bismuth_html::search::SearchFormat;
     #[test]
     fn simple_config() {
         let content = r#"
@@ -164,11 +273,12 @@ std = true
             website: WebsiteConfig {
                 name: String::from("test"),
                 std: true,
+                ..Default::default()
             },
             theme: Some(theme),
             ..Default::default()
         };
-        let result = Config::new_toml_config(&content);
+        let result = Config::new_toml_config(content);
         assert_eq!(expected, result)
     }
 
@@ -200,14 +310,251 @@ text_1 = "#fefefe"
             website: WebsiteConfig {
                 name: String::from("test"),
                 std: true,
+                ..Default::default()
             },
             theme: Some(theme),
             ..Default::default()
         };
-        let result = Config::new_toml_config(&content);
+        let result = Config::new_toml_config(content);
         assert_eq!(expected, result)
     }
 
+    #[test]
+    fn footer_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[footer]
+enabled = true
+copyright = "Made by me"
+links = [
+    { title = "GitHub", url = "https://github.com" },
+]
+"#;
+
+        let result = Config::new_toml_config(content);
+        let expected = FooterConfig {
+            enabled: true,
+            copyright: Some(String::from("Made by me")),
+            links: vec![FooterLink {
+                title: String::from("GitHub"),
+                url: String::from("https://github.com"),
+            }],
+        };
+        assert_eq!(Some(expected), result.footer)
+    }
+
+    #[test]
+    fn build_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[build]
+out = "public"
+static = "files"
+ignore = ["*.tmp"]
+fingerprint = true
+strict_links = true
+"#;
+
+        let result = Config::new_toml_config(content);
+        let expected = BuildConfig {
+            out: String::from("public"),
+            static_files: String::from("files"),
+            ignore: vec![String::from("*.tmp")],
+            fingerprint: true,
+            strict_links: true,
+            ..Default::default()
+        };
+        assert_eq!(Some(expected), result.build)
+    }
+
+    #[test]
+    fn image_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[images]
+sizes = [320, 640]
+formats = ["avif"]
+quality = 60
+"#;
+
+        let result = Config::new_toml_config(content);
+        let expected = ImageConfig {
+            sizes: vec![320, 640],
+            formats: vec![String::from("avif")],
+            quality: 60,
+            ..Default::default()
+        };
+        assert_eq!(Some(expected), result.images)
+    }
+
+    #[test]
+    fn highlight_config() {
+        let content = r##"
+[website]
+name = "test"
+std = true
+
+[theme]
+background_1 = "#fbf1c7"
+
+[highlight]
+mode = "class"
+theme = "themes/custom.tmTheme"
+
+[highlight.aliases]
+tf = "terraform"
+"##;
+
+        let result = Config::new_toml_config(content);
+        let mut expected = HighlightConfig {
+            mode: HighlightMode::Class,
+            theme: String::from("themes/custom.tmTheme"),
+            ..Default::default()
+        };
+        expected
+            .aliases
+            .insert(String::from("tf"), String::from("terraform"));
+        assert_eq!(Some(expected), result.highlight);
+        assert!(!result.theme.unwrap().is_dark());
+        assert!(Theme::default().fill_default().is_dark());
+    }
+
+    #[test]
+    fn snippets_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[snippets.commands]
+game = "cargo test"
+"#;
+
+        let result = Config::new_toml_config(content).snippets.unwrap();
+        assert_eq!(result.dir, "snippets");
+        assert_eq!(result.commands["game"], "cargo test");
+    }
+
+    #[test]
+    fn search_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[search]
+enabled = true
+format = "elasticlunr"
+"#;
+
+        let result = Config::new_toml_config(content).search.unwrap();
+        let expected = SearchConfig {
+            enabled: true,
+            format: SearchFormat::Elasticlunr,
+            ..Default::default()
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn seo_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[seo]
+lang = "nl"
+twitter = "@test"
+"#;
+
+        let result = Config::new_toml_config(content).seo.unwrap();
+        let expected = SeoConfig {
+            lang: String::from("nl"),
+            twitter: Some(String::from("@test")),
+            ..Default::default()
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn urls_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+base_url = "https://example.com/docs/"
+urls = "relative"
+"#;
+
+        let result = Config::new_toml_config(content).website;
+        let expected = WebsiteConfig {
+            name: String::from("test"),
+            std: true,
+            base_url: String::from("https://example.com/docs/"),
+            urls: UrlMode::Relative,
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn permalinks_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[permalinks]
+pretty = true
+
+[permalinks.dirs]
+blogs = "/blog/{year}/{month}/{slug}/"
+
+[permalinks.kinds]
+taxonomy = "/topics/{slug}/"
+"#;
+
+        let result = Config::new_toml_config(content).permalinks.unwrap();
+        let expected = PermalinkConfig {
+            pretty: true,
+            dirs: [(
+                String::from("blogs"),
+                String::from("/blog/{year}/{month}/{slug}/"),
+            )]
+            .into(),
+            kinds: [(String::from("taxonomy"), String::from("/topics/{slug}/"))].into(),
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn redirects_config() {
+        let content = r#"
+[website]
+name = "test"
+std = true
+
+[redirects]
+file = true
+"#;
+
+        let result = Config::new_toml_config(content).redirects.unwrap();
+        let expected = RedirectConfig {
+            file: true,
+            status: 301,
+        };
+        assert_eq!(result, expected);
+    }
+
     #[test]
     #[should_panic]
     fn simple_error() {
@@ -216,7 +563,7 @@ text_1 = "#fefefe"
 [website]
 "#;
 
-        let _ = Config::new_toml_config(&content);
+        let _ = Config::new_toml_config(content);
     }
 
     #[test]
@@ -227,15 +574,24 @@ name = "test"
 std = true
 "#;
 
-        let toml = Config::new_toml_config(&content);
+        let toml = Config::new_toml_config(content);
         let theme = toml.theme.unwrap_or_default().fill_default();
         let path = Path::new("./");
         let result = Config {
             name: String::new(),
             theme,
             addons: Default::default(),
-            directory: &path,
+            directory: path,
             bstd: true,
+            footer: Default::default(),
+            build: Default::default(),
+            images: Default::default(),
+            highlight: Default::default(),
+            snippets: Default::default(),
+            search: Default::default(),
+            seo: Default::default(),
+            urls: Default::default(),
+            redirects: Default::default(),
         }
         .gen_colors();
 
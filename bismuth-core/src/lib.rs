@@ -2,12 +2,37 @@ mod arguments;
 mod build;
 pub mod config;
 mod init;
+mod snippets;
 
 pub fn entry(dir: String) {
     let args = arguments::parse_args();
 
     match args.command {
-        arguments::Commands::Build { noconfirm } => build::build(dir, noconfirm),
+        arguments::Commands::Build {
+            noconfirm,
+            drafts,
+            future,
+            out,
+            src,
+            fingerprint,
+            minify,
+            strict_links,
+        } => build::build(
+            dir,
+            build::BuildOptions {
+                noconfirm,
+                drafts,
+                future,
+                out,
+                src,
+                fingerprint,
+                minify,
+                strict_links,
+                ..Default::default()
+            },
+        ),
+        arguments::Commands::Check => build::check(dir),
+        arguments::Commands::CheckSnippets => snippets::check_snippets(dir),
         arguments::Commands::Init { name } => {
             init::init_folder(&name).unwrap();
         }
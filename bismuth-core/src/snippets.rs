@@ -0,0 +1,164 @@
+use bismuth_custom::{config::SnippetsConfig, snippet};
+use bismuth_html::write::Dirs;
+use bismuth_parser::Parser;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::build::load_pages;
+use crate::config::Config;
+
+/// The command to check a code base with, based on the files in it
+/// Ie. `cargo build` if there is a `Cargo.toml`
+pub fn detect_command(dir: &Path) -> Option<&'static str> {
+    [
+        ("Cargo.toml", "cargo build"),
+        ("go.mod", "go build ./..."),
+        ("package.json", "npm run build --if-present"),
+        ("Makefile", "make"),
+    ]
+    .into_iter()
+    .find(|(file, _)| dir.join(file).exists())
+    .map(|(_, command)| command)
+}
+
+fn command(project: &str, dir: &Path, config: &SnippetsConfig) -> Option<String> {
+    config
+        .commands
+        .get(project)
+        .cloned()
+        .or_else(|| detect_command(dir).map(String::from))
+}
+
+fn run(command: &str, dir: &Path) -> Result<(), String> {
+    let mut shell = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    let output = shell
+        .arg(command)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+/// Builds every state of every code base that is used by a snippet
+/// Failures are reported at each place in the markdown that uses that state
+pub fn check_snippets(dir: String) {
+    let path = Path::new(&dir).canonicalize().unwrap();
+    let config = Config::new(&path);
+    let custom_config = config.custom_config();
+    let dirs = Dirs::new(
+        &path,
+        &config.build.src,
+        &config.build.out,
+        &config.build.assets,
+        &config.build.static_files,
+    );
+    let src = dirs.src.strip_prefix(&path).unwrap_or(&dirs.src);
+
+    let parsers = load_pages(&dirs.src);
+    let refs = snippet::find(&parsers.iter().collect::<Vec<&Parser>>());
+
+    // each state is only built once
+    let mut states = BTreeMap::new();
+    for r in refs {
+        states
+            .entry((r.project.clone(), r.state.clone()))
+            .or_insert_with(Vec::new)
+            .push(r);
+    }
+    println!("Checking {} snippet states...", states.len());
+
+    let mut failed = 0;
+    for ((project, state), refs) in &states {
+        let state_dir = refs[0].dir(&custom_config);
+        let result = if !state_dir.is_dir() {
+            Err(format!("{} does not exist", state_dir.to_string_lossy()))
+        } else {
+            match command(project, &state_dir, &config.snippets) {
+                Some(command) => run(&command, &state_dir),
+                None => Err(format!(
+                    "Do not know how to build it, set `[snippets.commands] {project} = \"...\"`"
+                )),
+            }
+        };
+
+        match result {
+            Ok(_) => println!("  {project}/{state}: ok"),
+            Err(output) => {
+                failed += 1;
+                println!("  {project}/{state}: failed");
+                for r in refs {
+                    let page = r.page.to_string_lossy();
+                    println!(
+                        "    {}/{}:{} ({})",
+                        src.to_string_lossy(),
+                        page.trim_start_matches(['/', '\\']),
+                        r.line,
+                        r.file
+                    );
+                }
+                for line in output.lines() {
+                    println!("      {line}");
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("{failed} of {} snippet states failed", states.len());
+        std::process::exit(1);
+    }
+    println!("All snippets passed!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn detect() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        assert_eq!(detect_command(dir), None);
+
+        fs::write(dir.join("Makefile"), "").unwrap();
+        assert_eq!(detect_command(dir), Some("make"));
+        fs::write(dir.join("Cargo.toml"), "").unwrap();
+        assert_eq!(detect_command(dir), Some("cargo build"));
+
+        let mut config = SnippetsConfig::default();
+        config
+            .commands
+            .insert(String::from("game"), String::from("cargo test"));
+        assert_eq!(
+            command("game", dir, &config),
+            Some(String::from("cargo test"))
+        );
+        assert_eq!(
+            command("other", dir, &config),
+            Some(String::from("cargo build"))
+        );
+
+        assert!(run("exit 0", dir).is_ok());
+        assert_eq!(
+            run("echo broken; exit 1", dir),
+            Err(String::from("broken\n"))
+        );
+    }
+}
@@ -1,11 +1,17 @@
 #![allow(dead_code)]
+use crate::bstd::{page, taxonomy};
 use crate::plugin::Plugin;
+use bismuth_error::warning::Warnings;
+use bismuth_md::MarkdownFile;
 use bismuth_parser::{
     custom::CustomElm,
+    slug::slugify,
     tree::{Element, Kind},
+    url::Urls,
     Parser,
 };
-use std::collections::HashMap;
+use chrono::NaiveDateTime;
+use std::collections::{BTreeMap, HashMap};
 
 pub const NAME1: &str = "blog list";
 pub const NAME2: &str = "blogs";
@@ -17,78 +23,361 @@ pub const ITEM: &str = include_str!("../../data/blog_item.html");
 pub const WRAPPER_NAME: &str = "builtin_blog_wrapper";
 pub const WRAPPER: &str = include_str!("../../data/blog_wrapper.html");
 
+pub const PAGINATION_NAME: &str = "builtin_blog_pagination";
+pub const PAGINATION: &str = include_str!("../../data/blog_pagination.html");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Sort {
+    #[default]
+    Date,
+    Title,
+    Order,
+}
+
+/// The options that can be passed to the custom element
+/// `sort: date|title|order`, `order: asc|desc`, `limit: N`, `filter_tag: tag` and `paginate: N`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ListOptions {
+    pub sort: Sort,
+    pub descending: bool,
+    pub limit: Option<usize>,
+    pub filter_tag: Option<String>,
+    pub paginate: Option<usize>,
+}
+
+impl ListOptions {
+    pub fn from_values(values: &HashMap<String, String>) -> Self {
+        let sort = match values.get("sort").map(|s| s.to_lowercase()).as_deref() {
+            Some("title") => Sort::Title,
+            Some("order") => Sort::Order,
+            _ => Sort::Date,
+        };
+        // newest posts first, everything else is alphabetical/numerical
+        let descending = match values.get("order").map(|s| s.to_lowercase()).as_deref() {
+            Some("asc") => false,
+            Some("desc") => true,
+            _ => sort == Sort::Date,
+        };
+        let parse_num = |key: &str| {
+            values
+                .get(key)
+                .and_then(|n| n.trim().parse::<usize>().ok())
+                .filter(|n| n > &0)
+        };
+
+        Self {
+            sort,
+            descending,
+            limit: parse_num("limit"),
+            filter_tag: values.get("filter_tag").cloned(),
+            paginate: parse_num("paginate"),
+        }
+    }
+}
+
+/// Gets the markdown file of a page in a paginated list, after the first
+/// Ie `/blogs.html` -> `/blogs/page/2.md`
+fn page_file(first_page: &str, page: usize) -> String {
+    let base = first_page
+        .trim_end_matches(".html")
+        .trim_end_matches("index")
+        .trim_end_matches('/');
+    format!("{base}/page/{page}.md")
+}
+
+/// Gets the url of a page in a paginated list, the pages after the first have the `kind` of the list
+/// Ie `/blogs.html` -> `/blogs/page/2.html`
+pub fn page_url(urls: &Urls, first_page: &str, page: usize, kind: &str) -> String {
+    if page <= 1 {
+        return first_page.to_string();
+    }
+    urls.file(&page_file(first_page, page), kind)
+}
+
+/// A blog post, with the fallbacks used for anything missing
+/// The title falls back to the file name, the date to when the file was last modified
+#[derive(Debug)]
+pub struct Post<'a> {
+    pub file: &'a Parser,
+    pub url: String,
+    pub title: String,
+    pub date: String,
+    parsed_date: Option<NaiveDateTime>,
+    order: Option<i32>,
+}
+
+impl<'a> Post<'a> {
+    /// `element` is the name of the element that is listing the post, used for warnings
+    pub fn new(
+        file: &'a Parser,
+        urls: &Urls,
+        element: &str,
+        sort: Sort,
+        warnings: &Warnings,
+    ) -> Self {
+        let (date, parsed_date) = page::date(warnings, file, element);
+        Post {
+            file,
+            url: urls.path(file),
+            title: page::title(file, &["title"]),
+            date,
+            parsed_date,
+            // only warn about a missing order if it is used
+            order: match sort {
+                Sort::Order => page::order(warnings, file, "order", element),
+                _ => None,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct BlogList {
     pub values: HashMap<String, String>,
     pub dir: String,
     pub id: u32,
+    pub options: ListOptions,
+
+    /// The page of the list that is being rendered, starting at 1
+    pub page: usize,
+    /// The url of the first page of the list
+    pub first_page: String,
+    /// The kind of the page the list is on
+    pub kind: String,
+    pub urls: Urls,
+    /// A problem with the values, found when loading and reported when the list is run
+    pub problem: Option<String>,
 }
 
 impl BlogList {
-    fn get_posts<'a>(&self, files: &[Option<&'a Parser>]) -> Vec<&'a bismuth_parser::Parser> {
+    fn get_posts<'a>(&self, files: &[&'a Parser]) -> Vec<&'a bismuth_parser::Parser> {
         let mut output_files = vec![];
         for file in files {
-            if file.is_some() {
-                let file = file.unwrap();
-                let file_path = file
-                    .metadata
-                    .frontmatter
-                    .get_path()
-                    .cloned()
-                    .unwrap_or_default();
-                if file_path == self.dir
-                    || format!("{file_path}/") == self.dir
-                    || format!("/{file_path}") == self.dir
-                {
-                    output_files.push(file);
-                }
+            let file_path = file
+                .metadata
+                .frontmatter
+                .get_path()
+                .cloned()
+                .unwrap_or_default();
+            if file_path == self.dir
+                || format!("{file_path}/") == self.dir
+                || format!("/{file_path}") == self.dir
+            {
+                output_files.push(*file);
             }
         }
         output_files
     }
 
-    fn gen_templates(&self, files: &[&Parser]) -> Vec<Element> {
-        let customs = files
-            .iter()
-            .map(|file| {
-                // get info
-                let frontmatter = &file.metadata.frontmatter;
-                let html_title = frontmatter.get_file_name().unwrap();
-                let path = frontmatter.get_path().unwrap();
-                let title = frontmatter.get_value("title").unwrap();
-                let date = frontmatter.get_value("date").unwrap();
+    /// Filters, sorts and limits the posts. This does not paginate them
+    pub fn filter_posts<'a>(&self, files: &[&'a Parser], warnings: &Warnings) -> Vec<Post<'a>> {
+        let mut posts = self.get_posts(files);
 
-                let full_path = format!("{path}/{html_title}.html");
+        if let Some(tag) = &self.options.filter_tag {
+            let tag = slugify(tag);
+            posts.retain(|post| {
+                taxonomy::get_terms(post, taxonomy::TAGS)
+                    .map(|tags| tags.iter().any(|t| slugify(t) == tag))
+                    .unwrap_or_default()
+            });
+        }
+
+        let mut posts = posts
+            .into_iter()
+            .map(|post| Post::new(post, &self.urls, NAME3, self.options.sort, warnings))
+            .collect::<Vec<Post>>();
+        self.sort_posts(&mut posts);
+
+        if let Some(limit) = self.options.limit {
+            posts.truncate(limit);
+        }
+        posts
+    }
+
+    pub fn sort_posts(&self, posts: &mut [Post]) {
+        let descending = self.options.descending;
+        posts.sort_by(|a, b| {
+            let ordering = match self.options.sort {
+                Sort::Date => page::cmp_missing_last(a.parsed_date, b.parsed_date, descending),
+                Sort::Title => page::cmp_missing_last(
+                    Some(a.title.to_lowercase()),
+                    Some(b.title.to_lowercase()),
+                    descending,
+                ),
+                Sort::Order => page::cmp_missing_last(a.order, b.order, descending),
+            };
+            // So the output does not depend on the order the files were loaded in
+            ordering.then_with(|| a.url.cmp(&b.url))
+        });
+    }
 
-                // make custom
+    pub fn gen_templates(&self, posts: &[Post]) -> Vec<Element> {
+        let current = page_url(&self.urls, &self.first_page, self.page, &self.kind);
+        posts
+            .iter()
+            .map(|post| {
                 let mut custom = CustomElm::new();
                 custom.name = String::from(ITEM_NAME);
                 custom
                     .values
-                    .insert(String::from("title"), String::from(title));
+                    .insert(String::from("title"), post.title.clone());
+                custom
+                    .values
+                    .insert(String::from("date"), post.date.clone());
                 custom
                     .values
-                    .insert(String::from("date"), String::from(date));
-                custom.values.insert(String::from("url"), full_path);
+                    .insert(String::from("url"), self.urls.url(&post.url, &current));
 
                 Element::new(Kind::CustomElement(custom))
             })
-            .collect::<Vec<Element>>();
-        customs
+            .collect::<Vec<Element>>()
+    }
+
+    fn gen_pagination(&self, pages: usize) -> Element {
+        let has_prev = self.page > 1;
+        let has_next = self.page < pages;
+
+        let current = page_url(&self.urls, &self.first_page, self.page, &self.kind);
+        let link = |page| {
+            self.urls.url(
+                &page_url(&self.urls, &self.first_page, page, &self.kind),
+                &current,
+            )
+        };
+
+        let mut custom = CustomElm::new();
+        custom.name = String::from(PAGINATION_NAME);
+        let values = [
+            ("page", self.page.to_string()),
+            ("pages", pages.to_string()),
+            ("has_prev", has_prev.to_string()),
+            ("has_next", has_next.to_string()),
+            ("prev", link(self.page - 1)),
+            ("next", link(self.page + 1)),
+        ];
+        for (key, value) in values {
+            custom.values.insert(String::from(key), value);
+        }
+        Element::new(Kind::CustomElement(custom))
+    }
+
+    /// Makes a page for every page of the list after the first
+    /// Ie `/blogs/page/2.html`, `/blogs/page/3.html`...
+    fn gen_pages(&self, target: &Parser, files: &[&Parser]) -> Vec<MarkdownFile> {
+        let per_page = match self.options.paginate {
+            Some(p) => p,
+            None => return vec![],
+        };
+        // the posts are checked when the list is run, so these warnings are not needed
+        let posts = self.filter_posts(files, &Warnings::default()).len();
+        let pages = posts.div_ceil(per_page);
+
+        let frontmatter = &target.metadata.frontmatter;
+        let title = frontmatter.get_title().cloned().unwrap_or_default();
+        let frontmatter = [("title", title.as_str()), ("kind", self.kind.as_str())];
+
+        // sorted so the output is always the same
+        let values = self
+            .values
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "name" | "page" | "first_page"))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<BTreeMap<_, _>>();
+
+        (2..=pages)
+            .map(|number| {
+                let page_number = number.to_string();
+                let mut pairs = vec![("name", NAME3)];
+                pairs.extend(values.iter().map(|(key, value)| (*key, *value)));
+                pairs.push(("page", &page_number));
+                pairs.push(("first_page", &self.first_page));
+                page::generate(page_file(&self.first_page, number), &frontmatter, &pairs)
+            })
+            .collect()
     }
 }
 
-impl Plugin for BlogList {
-    fn pre_load(&mut self, _: &Parser, custom: &crate::Custom) {
-        self.values = custom.data.clone();
+fn find_lists(elements: &[Element], lists: &mut Vec<HashMap<String, String>>) {
+    for element in elements {
+        if let Kind::CustomElement(c) = &element.kind {
+            if matches!(c.name.as_str(), NAME1 | NAME2 | NAME3) {
+                lists.push(c.values.clone());
+            }
+        }
+        find_lists(&element.elements, lists);
+    }
+}
+
+/// Makes the extra pages for all the paginated lists
+pub fn gen_pages(files: &[&Parser], urls: &Urls) -> Vec<MarkdownFile> {
+    let mut pages = vec![];
+    for file in files {
+        let mut lists = vec![];
+        find_lists(&file.ast.elements, &mut lists);
+        for values in lists {
+            let mut list = BlogList::default();
+            list.load(file, values, urls);
+            pages.append(&mut list.gen_pages(file, files));
+        }
+    }
+    pages
+}
+
+impl BlogList {
+    fn load(&mut self, target: &Parser, values: HashMap<String, String>, urls: &Urls) {
+        self.values = values;
+        self.urls = urls.clone();
         self.dir = self.values.get("dir").cloned().unwrap_or_default();
+        self.options = ListOptions::from_values(&self.values);
+        // pages start at 1, anything else would make the list skip posts that do not exist
+        self.page = match self.values.get("page") {
+            None => 1,
+            Some(p) => match p.parse::<usize>() {
+                Ok(page) if page >= 1 => page,
+                _ => {
+                    self.problem =
+                        Some(format!("`page: {p}` is not a page, it should be 1 or more"));
+                    1
+                }
+            },
+        };
+        self.first_page = self
+            .values
+            .get("first_page")
+            .cloned()
+            .unwrap_or_else(|| urls.path(target));
+        self.kind = target
+            .metadata
+            .frontmatter
+            .get_kind()
+            .cloned()
+            .unwrap_or_default();
+    }
+}
+
+impl Plugin for BlogList {
+    fn pre_load(&mut self, target: &Parser, custom: &crate::Custom) {
+        self.load(target, custom.data.clone(), &custom.config.urls);
         self.id = custom.id;
     }
 
-    fn run(&mut self, target: &mut Parser, files: &[Option<&Parser>]) {
-        let mod_element = target.ast.find_mut(self.id).unwrap();
+    fn run(&mut self, target: &mut Parser, files: &[Option<&Parser>], warnings: &Warnings) {
+        if let Some(problem) = self.problem.take() {
+            page::report(warnings, target, NAME3, problem);
+        }
+        let files = files.iter().flatten().copied().collect::<Vec<&Parser>>();
+        let mut posts = self.filter_posts(&files, warnings);
+
+        let mut pagination = None;
+        if let Some(per_page) = self.options.paginate {
+            let pages = posts.len().div_ceil(per_page);
+            let start = (self.page - 1) * per_page;
+            posts = posts.into_iter().skip(start).take(per_page).collect();
+            if pages > 1 {
+                pagination = Some(self.gen_pagination(pages));
+            }
+        }
 
-        let posts = self.get_posts(files);
         let mut customs = self.gen_templates(&posts);
 
         let mut wrapper = CustomElm::new();
@@ -96,6 +385,154 @@ impl Plugin for BlogList {
         let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
         wrapper_elm.elements.append(&mut customs);
 
+        let mod_element = target.ast.find_mut(self.id).unwrap();
         mod_element.elements.push(wrapper_elm);
+        if let Some(pagination) = pagination {
+            mod_element.elements.push(pagination);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CustomConfig;
+    use crate::testing::{customs, page, run, snapshot, values};
+
+    fn blog_posts() -> Vec<Parser> {
+        [
+            ("/blogs/old.md", "Old", "2021-01-01", "2", "tags: [rust]"),
+            ("/blogs/new.md", "New", "2023-05-01", "3", ""),
+            ("/blogs/mid.md", "Mid", "2022-03-01", "1", "tags: [Rust]"),
+            ("/blogs/undated.md", "Undated", "someday", "", ""),
+            ("/other/page.md", "Other", "2024-01-01", "", ""),
+        ]
+        .iter()
+        .map(|(path, title, date, order, tags)| {
+            page(path, &format!(
+                "---\nvalues:\n    - title: {title}\n    - date: {date}\n    - order: \"{order}\"\n{tags}\n---\n# Post"
+            ))
+        })
+        .collect()
+    }
+
+    fn list_items(
+        list: &str,
+        posts: &[Parser],
+        warnings: &Warnings,
+    ) -> Vec<HashMap<String, String>> {
+        let posts = posts.iter().collect::<Vec<&Parser>>();
+        let parser = run(
+            page("/blogs.md", list),
+            &posts,
+            &CustomConfig::default(),
+            warnings,
+        );
+        customs(&parser.ast.elements, ITEM_NAME)
+            .into_iter()
+            .map(|item| values(item).clone())
+            .collect()
+    }
+
+    fn list_titles(list: &str, posts: &[Parser]) -> Vec<String> {
+        list_items(list, posts, &Warnings::default())
+            .into_iter()
+            .map(|item| item["title"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn bloglist_options_test() {
+        let posts = blog_posts();
+        let list = |options: &str| {
+            list_titles(
+                &format!("%{{{{\nname: bloglist\ndir: /blogs\n{options}}}}}"),
+                &posts,
+            )
+        };
+
+        assert_eq!(list(""), ["New", "Mid", "Old", "Undated"]);
+        assert_eq!(list("order: asc\n"), ["Old", "Mid", "New", "Undated"]);
+        assert_eq!(list("sort: title\n"), ["Mid", "New", "Old", "Undated"]);
+        assert_eq!(list("sort: order\n"), ["Mid", "Old", "New", "Undated"]);
+        assert_eq!(list("limit: 2\n"), ["New", "Mid"]);
+        assert_eq!(list("filter_tag: rust\n"), ["Mid", "Old"]);
+        assert_eq!(list("paginate: 3\n"), ["New", "Mid", "Old"]);
+        assert_eq!(
+            list("paginate: 3\npage: 2\nfirst_page: /blogs.html\n"),
+            ["Undated"]
+        );
+    }
+
+    #[test]
+    fn missing_frontmatter_test() {
+        let mut empty = page(
+            "/blogs/empty.md",
+            "---\nvalues:\n    - navbar_include: true\n---\n# Empty",
+        );
+        empty.metadata.modified = bismuth_parser::date::parse_date("2023-01-02");
+
+        let warnings = Warnings::default();
+        let items = list_items("%{{\nname: bloglist\ndir: /blogs\n}}", &[empty], &warnings);
+        assert_eq!(items[0]["title"], "empty");
+        assert_eq!(items[0]["date"], "2023-01-02");
+
+        // pages do not need a title, so only the missing order and date are warnings
+        let warnings = warnings
+            .take_warnings()
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            warnings,
+            [
+                "/blogs/empty.md (bloglist): missing `date`, using the last modified date (2023-01-02)",
+                "/blogs/empty.md (navbar): missing `navbar_order`, putting it last",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_page_test() {
+        let posts = blog_posts();
+        for page in ["0", "two"] {
+            let warnings = Warnings::default();
+            let list =
+                format!("%{{{{\nname: bloglist\ndir: /blogs\npaginate: 3\npage: {page}\n}}}}");
+            let titles = list_items(&list, &posts, &warnings)
+                .into_iter()
+                .map(|item| item["title"].clone())
+                .collect::<Vec<String>>();
+            assert_eq!(titles, ["New", "Mid", "Old"]);
+
+            let warnings = warnings
+                .take_warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<String>>();
+            assert_eq!(
+                warnings,
+                [format!(
+                    "/blogs.md (bloglist): `page: {page}` is not a page, it should be 1 or more"
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn bloglist_pages_test() {
+        let posts = blog_posts();
+        let list = page(
+            "/blogs.md",
+            "---\ntitle: \"Blogs: all #posts\"\n---\n%{{\nname: bloglist\ndir: /blogs\nsort: title\npaginate: 2\n}}",
+        );
+
+        let mut files = posts.iter().collect::<Vec<&Parser>>();
+        files.push(&list);
+        let pages = gen_pages(&files, &Default::default())
+            .iter()
+            .map(|p| format!("{}:\n{}\n", p.path.to_string_lossy(), p.content))
+            .collect::<String>();
+        snapshot!(pages);
     }
 }
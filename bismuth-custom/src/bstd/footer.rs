@@ -1 +1,220 @@
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    custom::CustomElm,
+    tree::{Element, Kind},
+    url::Urls,
+    Parser,
+};
+use chrono::{Datelike, Local};
+
+use crate::bstd::page;
+use crate::config::FooterLink;
+use crate::plugin::Plugin;
+
 pub const NAME: &str = "footer";
+
+pub const WRAPPER_NAME: &str = "bismuth_footer_wrapper";
+pub const WRAPPER: &str = include_str!("../../data/footer_wrapper.html");
+
+pub const ITEM_NAME: &str = "bismuth_footer_item";
+pub const ITEM: &str = include_str!("../../data/footer_item.html");
+
+/// If the footer should be auto added to a page
+/// Pages can opt out with `footer_include: false`
+pub fn should_add(target: &Parser) -> bool {
+    target
+        .metadata
+        .frontmatter
+        .get_value("footer_include")
+        .map(|i| i.to_lowercase() != "false")
+        .unwrap_or(true)
+}
+
+pub fn add_footer(target: &mut Parser) -> u32 {
+    let mut customelm = CustomElm::new();
+    customelm.name = String::from(NAME);
+    let element = Element::new(Kind::CustomElement(customelm));
+    let id = element.get_id();
+    target.ast.elements.push(element);
+    id
+}
+
+#[derive(Debug, Default)]
+pub struct Footer {
+    pub copyright: String,
+    pub links: Vec<FooterLink>,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
+    pub id: u32,
+    /// A problem with the links of the page, found when loading and reported when the footer is run
+    pub problem: Option<String>,
+}
+
+impl Footer {
+    fn gen_elements(&self) -> Vec<Element> {
+        self.links
+            .iter()
+            .map(|link| {
+                let mut custom = CustomElm::new();
+                custom.name = String::from(ITEM_NAME);
+                custom
+                    .values
+                    .insert(String::from("title"), link.title.clone());
+                custom
+                    .values
+                    .insert(String::from("url"), self.urls.url(&link.url, &self.url));
+
+                Element::new(Kind::CustomElement(custom))
+            })
+            .collect::<Vec<Element>>()
+    }
+}
+
+impl Plugin for Footer {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        let config = &custom.config.footer;
+        let frontmatter = &page.metadata.frontmatter;
+
+        // the element, then the page, then the config can set the copyright
+        self.copyright = custom
+            .data
+            .get("copyright")
+            .or(frontmatter.get_value("footer_copyright"))
+            .cloned()
+            .or(config.copyright.clone())
+            .unwrap_or_else(|| format!("© {} {}", Local::now().year(), custom.config.name));
+        // the links are yaml, ie. `[{title: Blog, url: /blogs.html}]`, and replace the ones in the config
+        self.links = match custom
+            .data
+            .get("links")
+            .or(frontmatter.get_value("footer_links"))
+        {
+            Some(links) => serde_yaml::from_str(links).unwrap_or_else(|e| {
+                self.problem = Some(format!(
+                    "the links could not be read, using the config: {e}"
+                ));
+                config.links.clone()
+            }),
+            None => config.links.clone(),
+        };
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, _: &[Option<&Parser>], warnings: &Warnings) {
+        if let Some(problem) = self.problem.take() {
+            page::report(warnings, target, NAME, problem);
+        }
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+
+        let mut wrapper = CustomElm::new();
+        wrapper.name = WRAPPER_NAME.to_string();
+        wrapper
+            .values
+            .insert(String::from("copyright"), self.copyright.clone());
+        let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
+        wrapper_elm.elements.append(&mut self.gen_elements());
+
+        mod_element.elements.push(wrapper_elm);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{CustomConfig, FooterConfig};
+    use crate::testing::{customs, page, run, values};
+    use std::collections::HashMap;
+
+    fn footers(content: &str, config: &CustomConfig) -> Vec<HashMap<String, String>> {
+        let parser = run(
+            page("/index.md", content),
+            &[],
+            config,
+            &Warnings::default(),
+        );
+        customs(&parser.ast.elements, WRAPPER_NAME)
+            .into_iter()
+            .map(|footer| values(footer).clone())
+            .collect()
+    }
+
+    fn links(content: &str, config: &CustomConfig, warnings: &Warnings) -> Vec<String> {
+        let parser = run(page("/blogs/post.md", content), &[], config, warnings);
+        customs(&parser.ast.elements, ITEM_NAME)
+            .into_iter()
+            .map(|item| format!("{} {}", values(item)["title"], values(item)["url"]))
+            .collect()
+    }
+
+    #[test]
+    fn footer_test() {
+        let mut config = CustomConfig {
+            name: String::from("Test"),
+            footer: FooterConfig {
+                enabled: false,
+                copyright: Some(String::from("Made by me")),
+                links: vec![FooterLink {
+                    title: String::from("GitHub"),
+                    url: String::from("https://github.com"),
+                }],
+            },
+            ..Default::default()
+        };
+        assert!(footers("# Page", &config).is_empty());
+
+        config.footer.enabled = true;
+        let footer = footers("# Page", &config);
+        assert_eq!(footer.len(), 1);
+        assert_eq!(footer[0]["copyright"], "Made by me");
+
+        let footer = footers(
+            "---\nvalues:\n    - footer_copyright: Just this page\n---\n# Page",
+            &config,
+        );
+        assert_eq!(footer[0]["copyright"], "Just this page");
+
+        let hidden = "---\nvalues:\n    - footer_include: false\n---\n# Page";
+        assert!(footers(hidden, &config).is_empty());
+    }
+
+    #[test]
+    fn footer_links_test() {
+        let mut config = CustomConfig::default();
+        config.footer.enabled = true;
+        config.footer.links = vec![FooterLink {
+            title: String::from("GitHub"),
+            url: String::from("https://github.com"),
+        }];
+
+        let warnings = Warnings::default();
+        assert_eq!(
+            links("# Post", &config, &warnings),
+            ["GitHub https://github.com"]
+        );
+
+        let page_links =
+            "---\nvalues:\n    - footer_links: \"[{title: Blog, url: /blogs.html}]\"\n---\n# Post";
+        assert_eq!(links(page_links, &config, &warnings), ["Blog /blogs.html"]);
+        let no_links = "---\nvalues:\n    - footer_links: \"[]\"\n---\n# Post";
+        assert!(links(no_links, &config, &warnings).is_empty());
+        assert!(warnings.take_warnings().is_empty());
+
+        let bad_links = "---\nvalues:\n    - footer_links: \"[{title: Blog}]\"\n---\n# Post";
+        assert_eq!(
+            links(bad_links, &config, &warnings),
+            ["GitHub https://github.com"]
+        );
+        let warnings = warnings
+            .take_warnings()
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            warnings,
+            ["/blogs/post.md (footer): the links could not be read, using the config: .[0]: missing field `url` at line 1 column 2"]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::bstd::{include_code, page};
+use crate::config::CustomConfig;
+use bismuth_error::warning::Warnings;
+use bismuth_lexer::Lexer;
+use bismuth_md::MarkdownFile;
+use bismuth_parser::{
+    heading::assign_ids,
+    tree::{Element, Kind},
+    Parser,
+};
+use chrono::{DateTime, Local, NaiveDateTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const NAME: &str = "include";
+
+struct Includer<'a> {
+    config: &'a CustomConfig,
+    /// The files that are being included, to find cycles
+    stack: Vec<PathBuf>,
+    /// The line in the page and the message
+    errors: Vec<(usize, String)>,
+    /// When the included file that was changed last was modified
+    modified: Option<NaiveDateTime>,
+}
+
+impl Includer<'_> {
+    /// Paths starting with `/` are relative to the project, the rest are relative to the file they are in
+    fn resolve(&self, file: &str, dir: &Path) -> PathBuf {
+        match file.strip_prefix('/') {
+            Some(file) => self.config.project.join(file),
+            None => dir.join(file),
+        }
+    }
+
+    fn display(&self, path: &Path) -> String {
+        path.strip_prefix(&self.config.project)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Lexes and parses a file, the frontmatter is not used
+    fn parse(&self, path: &Path) -> Result<Vec<Element>, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("could not read `{}`: {e}", self.display(path)))?;
+        let mut lexer = Lexer::new(MarkdownFile {
+            content,
+            path: PathBuf::from(self.display(path)),
+        });
+        lexer
+            .run_lexer()
+            .map_err(|e| format!("could not lex `{}`: {e:?}", self.display(path)))?;
+        let mut parser = Parser::new(lexer);
+        parser
+            .parse()
+            .map_err(|e| format!("could not parse `{}`: {e}", self.display(path)))?;
+        Ok(parser.ast.elements)
+    }
+
+    fn include(&mut self, file: &str, dir: &Path, line: usize) -> Result<Vec<Element>, String> {
+        let path = self.resolve(file, dir);
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("could not read `{}`: {e}", self.display(&path)))?;
+        if self.stack.contains(&path) {
+            let chain = self
+                .stack
+                .iter()
+                .chain([&path])
+                .map(|p| self.display(p))
+                .collect::<Vec<String>>()
+                .join(" -> ");
+            return Err(format!(
+                "`{}` includes itself ({chain})",
+                self.display(&path)
+            ));
+        }
+
+        let elements = self.parse(&path)?;
+        let modified = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|m| DateTime::<Local>::from(m).naive_local());
+        self.modified = self.modified.max(modified);
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.stack.push(path);
+        let elements = self.expand(elements, &dir, Some(line));
+        self.stack.pop();
+        Ok(elements)
+    }
+
+    /// Replaces the includes in `elements` with the elements of the included files
+    /// `line` is the line of the include in the page, if these elements are from a included file
+    fn expand(&mut self, elements: Vec<Element>, dir: &Path, line: Option<usize>) -> Vec<Element> {
+        let mut expanded = vec![];
+        for mut element in elements {
+            if let Kind::CustomElement(c) = &mut element.kind {
+                // code in a included file is relative to that file, not the page
+                if line.is_some() && c.name == include_code::NAME {
+                    if let Some(file) = c.values.get_mut("file") {
+                        *file = dir.join(&*file).to_string_lossy().to_string();
+                    }
+                }
+                // errors in included files are shown at the include in the page
+                let line = line.unwrap_or(c.line);
+                c.line = line;
+                if c.name == NAME {
+                    let included = match c.values.get("file") {
+                        Some(file) => self.include(&file.clone(), dir, line),
+                        None => Err(String::from("`file` is needed")),
+                    };
+                    match included {
+                        Ok(mut elements) => expanded.append(&mut elements),
+                        Err(e) => self.errors.push((line, e)),
+                    }
+                    continue;
+                }
+            }
+            // links in included files are shown at the include too
+            if let (Some(line), true) = (line, element.attrs.contains_key("line")) {
+                element.add_attr("line", &line);
+            }
+            element.elements = self.expand(std::mem::take(&mut element.elements), dir, line);
+            expanded.push(element);
+        }
+        expanded
+    }
+}
+
+/// Replaces every `include` element in a page with the parsed content of the file
+/// This is done before running the other customs, so the customs in the included files are run
+/// The page is modified when the files it includes are
+pub fn expand(target: &mut Parser, config: &CustomConfig, warnings: &Warnings) {
+    let path = config.src.join(
+        target
+            .metadata
+            .absolute_path
+            .to_string_lossy()
+            .trim_start_matches(['/', '\\']),
+    );
+    let mut includer = Includer {
+        config,
+        stack: vec![path.canonicalize().unwrap_or(path)],
+        errors: vec![],
+        modified: None,
+    };
+
+    let elements = std::mem::take(&mut target.ast.elements);
+    let dir = page::dir(target, &config.src);
+    target.ast.elements = includer.expand(elements, &dir, None);
+    // the headings of each file were given ids on their own, so they can be the same as the ones on the page
+    assign_ids(&mut target.ast);
+    if includer.modified.is_some() {
+        target.metadata.modified = target.metadata.modified.max(includer.modified);
+    }
+    for (line, message) in includer.errors {
+        page::fail(warnings, target, NAME, line, message);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{errors, page, run};
+    use bismuth_test::project;
+
+    fn texts(elements: &[Element], found: &mut Vec<String>) {
+        for element in elements {
+            if let Some(text) = &element.text {
+                found.push(text.clone());
+            }
+            texts(&element.elements, found);
+        }
+    }
+
+    #[test]
+    fn include_test() {
+        let warnings = Warnings::default();
+        let content = "# Page\n\n%{{\nname: include\nfile: ../partials/disclaimer.md\n}}\n\n%{{\nname: include\nfile: /partials/a.md\n}}\n\n%{{\nname: include\nfile: /partials/code.md\n}}\n";
+        let dir = project(&[
+            ("src/include.md", content),
+            (
+                "partials/disclaimer.md",
+                "---\ntitle: Disclaimer\n---\nNot advice\n\n%{{\nname: include\nfile: sign.md\n}}\n",
+            ),
+            ("partials/sign.md", "Signed\n"),
+            ("partials/a.md", "A\n\n%{{\nname: include\nfile: b.md\n}}\n"),
+            ("partials/b.md", "B\n\n%{{\nname: include\nfile: a.md\n}}\n"),
+            (
+                "partials/code.md",
+                "%{{\nname: include_code\nfile: code.py\n}}\n",
+            ),
+            ("partials/code.py", "print(\"from the partial\")\n"),
+        ]);
+        let project = dir.path().canonicalize().unwrap();
+        let config = CustomConfig {
+            src: project.join("src"),
+            project,
+            ..Default::default()
+        };
+
+        let mut parser = page("/include.md", content);
+        let old = bismuth_parser::date::parse_date("2000-01-01");
+        parser.metadata.modified = old;
+        expand(&mut parser, &config, &warnings);
+        let parser = run(parser, &[], &config, &warnings);
+        // the included files were modified after the page
+        assert!(parser.metadata.modified > old);
+
+        let mut found = vec![];
+        texts(&parser.ast.elements, &mut found);
+        let found = found.join("|");
+        assert!(found.contains("Not advice"));
+        assert!(found.contains("Signed"));
+        assert!(!found.contains("Disclaimer"));
+        assert!(found.contains("A|"));
+        assert!(found.contains("from the partial"));
+
+        assert_eq!(
+            errors(&warnings),
+            ["/include.md:8 (include): `partials/a.md` includes itself (src/include.md -> partials/a.md -> partials/b.md -> partials/a.md)"]
+        );
+    }
+    #[test]
+    fn heading_ids_test() {
+        let warnings = Warnings::default();
+        let content = "# Setup\n\n%{{\nname: include\nfile: /partials/step.md\n}}\n\n%{{\nname: include\nfile: /partials/step.md\n}}\n";
+        let dir = project(&[
+            ("src/steps.md", content),
+            ("partials/step.md", "## Setup\n\nRun it\n"),
+        ]);
+        let project = dir.path().canonicalize().unwrap();
+        let config = CustomConfig {
+            src: project.join("src"),
+            project,
+            ..Default::default()
+        };
+
+        let mut parser = page("/steps.md", content);
+        expand(&mut parser, &config, &warnings);
+        assert!(errors(&warnings).is_empty());
+
+        let ids = parser
+            .ast
+            .elements
+            .iter()
+            .filter(|e| e.kind == Kind::Header)
+            .map(|e| e.get_attr("id").unwrap().clone())
+            .collect::<Vec<String>>();
+        assert_eq!(ids, ["setup", "setup-1", "setup-2"]);
+    }
+}
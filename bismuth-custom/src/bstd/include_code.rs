@@ -0,0 +1,109 @@
+use crate::bstd::{page, snippet};
+use crate::plugin::Plugin;
+use bismuth_error::warning::Warnings;
+use bismuth_parser::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub const NAME: &str = "include_code";
+
+/// Includes code from a file, the path is relative to the page
+/// Then either `lines` (ie. `10-30`) or `region` (between `ANCHOR: name` and `ANCHOR_END: name`), if there are neither the whole file is used
+/// `title`, `highlight`, `linenos` and `lang` are the same as the options for a ``` fence
+#[derive(Debug, Default)]
+pub struct IncludeCode {
+    id: u32,
+    line: usize,
+    values: HashMap<String, String>,
+    dir: PathBuf,
+    project: PathBuf,
+}
+
+impl Plugin for IncludeCode {
+    fn pre_load(&mut self, target: &Parser, custom: &crate::Custom) {
+        self.id = custom.id;
+        self.line = custom.line;
+        self.values = custom.data.clone();
+        self.dir = page::dir(target, &custom.config.src);
+        self.project = custom.config.project.clone();
+    }
+
+    fn run(&mut self, target: &mut Parser, _files: &[Option<&Parser>], warnings: &Warnings) {
+        match self.values.get("file") {
+            Some(file) => {
+                let path = match snippet::in_project(&self.dir.join(file), &self.project) {
+                    Ok(p) => p,
+                    Err(e) => return page::fail(warnings, target, NAME, self.line, e),
+                };
+                // the path is relative to the page, so only the name is useful as a title
+                let mut values = self.values.clone();
+                if let Some(name) = path.file_name() {
+                    values
+                        .entry(String::from("title"))
+                        .or_insert(name.to_string_lossy().to_string());
+                }
+                snippet::insert(warnings, target, NAME, self.id, self.line, &path, &values);
+            }
+            None => page::fail(
+                warnings,
+                target,
+                NAME,
+                self.line,
+                String::from("`file` is needed"),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CustomConfig;
+    use crate::testing::{errors, page, run};
+    use bismuth_parser::tree::{Element, Kind};
+    use bismuth_test::project;
+
+    #[test]
+    fn include_code_test() {
+        let warnings = Warnings::default();
+        let dir = project(&[
+            (
+                "site/examples/foo.py",
+                "import os\n\ndef main():\n    print(os.name)\n",
+            ),
+            ("site/src/blogs/include.md", ""),
+            ("outside.py", "print(\"not in the site\")\n"),
+        ]);
+        let config = CustomConfig {
+            project: dir.path().join("site"),
+            src: dir.path().join("site/src"),
+            ..Default::default()
+        };
+
+        let content = "%{{\nname: include_code\nfile: ../../examples/foo.py\nlines: 3-4\nlinenos: true\n}}\n\n%{{\nname: include_code\nfile: ../../examples/foo.py\nlines: 4-9\n}}\n\n%{{\nname: include_code\nfile: ../../../outside.py\n}}\n";
+        let parser = run(page("/blogs/include.md", content), &[], &config, &warnings);
+
+        let code = parser
+            .ast
+            .elements
+            .iter()
+            .flat_map(|e| &e.elements)
+            .filter(|e| e.kind == Kind::BlockCode)
+            .collect::<Vec<&Element>>();
+        assert_eq!(code.len(), 1);
+        assert_eq!(
+            code[0].text.as_deref(),
+            Some("\ndef main():\n    print(os.name)")
+        );
+        assert_eq!(code[0].get_attr("lang").unwrap(), "py");
+        assert_eq!(code[0].get_attr("title").unwrap(), "foo.py");
+        assert_eq!(code[0].get_attr("linenos").unwrap(), "true");
+
+        let errors = errors(&warnings);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("/blogs/include.md:8 (include_code): "));
+        assert!(errors[0].ends_with("lines `4-9` are out of range, the file has 4 lines"));
+        assert!(errors[1].starts_with("/blogs/include.md:14 (include_code): "));
+        assert!(errors[1].ends_with("outside.py` is not in the project"));
+    }
+}
@@ -1,24 +1,77 @@
 pub mod bloglist;
 pub mod footer;
+pub mod include;
+pub mod include_code;
 pub mod navbar;
+pub mod page;
+pub mod search;
+pub mod series;
+pub mod snippet;
+pub mod taxonomy;
+pub mod toc;
+pub mod wiki;
+
+use bismuth_error::warning::Warnings;
+use bismuth_md::MarkdownFile;
+use bismuth_parser::{url::Urls, Parser};
 
 use crate::{plugin::Plugin, template::Template};
 
+pub fn gen_pages(files: &[&Parser], urls: &Urls, warnings: &Warnings) -> Vec<MarkdownFile> {
+    let mut pages = taxonomy::gen_pages(files, warnings);
+    pages.append(&mut bloglist::gen_pages(files, urls));
+    pages.append(&mut series::gen_pages(files));
+    pages
+}
+
 pub fn match_template(name: &str) -> Option<Template> {
     match name {
-        footer::NAME => Some(Template {
-            content: "<h1>Test template</h1>".to_string(),
-        }),
+        // Footer
+        footer::ITEM_NAME => Some(Template::new(footer::ITEM.to_string().replace('\r', ""))),
+        footer::WRAPPER_NAME => Some(Template::new(footer::WRAPPER.to_string().replace('\r', ""))),
 
         // Wrapper
         bloglist::ITEM_NAME => Some(Template::new(bloglist::ITEM.to_string().replace('\r', ""))),
         bloglist::WRAPPER_NAME => Some(Template::new(
             bloglist::WRAPPER.to_string().replace('\r', ""),
         )),
+        bloglist::PAGINATION_NAME => Some(Template::new(
+            bloglist::PAGINATION.to_string().replace('\r', ""),
+        )),
 
         // Navbar
         navbar::ITEM_NAME => Some(Template::new(navbar::ITEM.to_string().replace('\r', ""))),
         navbar::WRAPPER_NAME => Some(Template::new(navbar::WRAPPER.to_string().replace('\r', ""))),
+
+        // Taxonomy
+        taxonomy::TAG_ITEM_NAME => Some(Template::new(
+            taxonomy::TAG_ITEM.to_string().replace('\r', ""),
+        )),
+        taxonomy::TAG_WRAPPER_NAME => Some(Template::new(
+            taxonomy::TAG_WRAPPER.to_string().replace('\r', ""),
+        )),
+        taxonomy::TERM_ITEM_NAME => Some(Template::new(
+            taxonomy::TERM_ITEM.to_string().replace('\r', ""),
+        )),
+        taxonomy::TERM_WRAPPER_NAME => Some(Template::new(
+            taxonomy::TERM_WRAPPER.to_string().replace('\r', ""),
+        )),
+
+        // Series
+        series::ITEM_NAME => Some(Template::new(series::ITEM.to_string().replace('\r', ""))),
+        series::WRAPPER_NAME => Some(Template::new(series::WRAPPER.to_string().replace('\r', ""))),
+
+        // Toc
+        toc::WRAPPER_NAME => Some(Template::new(toc::WRAPPER.to_string().replace('\r', ""))),
+        toc::LIST_NAME => Some(Template::new(toc::LIST.to_string().replace('\r', ""))),
+        toc::ITEM_NAME => Some(Template::new(toc::ITEM.to_string().replace('\r', ""))),
+
+        // Backlinks
+        wiki::ITEM_NAME => Some(Template::new(wiki::ITEM.to_string().replace('\r', ""))),
+        wiki::WRAPPER_NAME => Some(Template::new(wiki::WRAPPER.to_string().replace('\r', ""))),
+
+        // Search
+        search::WRAPPER_NAME => Some(Template::new(search::WRAPPER.to_string().replace('\r', ""))),
         _ => None,
     }
 }
@@ -28,10 +81,37 @@ pub fn match_plugin(name: &str) -> Option<Box<dyn Plugin>> {
         #[allow(clippy::box_default)]
         navbar::NAME => Some(Box::new(navbar::Navbar::default())),
 
+        #[allow(clippy::box_default)]
+        footer::NAME => Some(Box::new(footer::Footer::default())),
+
         #[allow(clippy::box_default)]
         bloglist::NAME1 | bloglist::NAME2 | bloglist::NAME3 => {
             Some(Box::new(bloglist::BlogList::default()))
         }
+
+        #[allow(clippy::box_default)]
+        taxonomy::NAME => Some(Box::new(taxonomy::Taxonomy::default())),
+
+        #[allow(clippy::box_default)]
+        taxonomy::TAGS_NAME => Some(Box::new(taxonomy::Tags::default())),
+
+        #[allow(clippy::box_default)]
+        series::NAME => Some(Box::new(series::Series::default())),
+
+        #[allow(clippy::box_default)]
+        toc::NAME => Some(Box::new(toc::Toc::default())),
+
+        #[allow(clippy::box_default)]
+        snippet::NAME => Some(Box::new(snippet::Snippet::default())),
+
+        #[allow(clippy::box_default)]
+        include_code::NAME => Some(Box::new(include_code::IncludeCode::default())),
+
+        #[allow(clippy::box_default)]
+        wiki::NAME => Some(Box::new(wiki::Backlinks::default())),
+
+        #[allow(clippy::box_default)]
+        search::NAME => Some(Box::new(search::Search::default())),
         _ => None,
     }
 }
@@ -1,10 +1,13 @@
+use bismuth_error::warning::Warnings;
 use bismuth_parser::{
     custom::CustomElm,
     tree::{Element, Kind},
+    url::Urls,
     Parser,
 };
 use std::collections::HashMap;
 
+use crate::bstd::page;
 use crate::plugin::Plugin;
 
 pub const NAME: &str = "navbar";
@@ -24,11 +27,13 @@ pub fn add_navbar(target: &mut Parser) -> u32 {
     id
 }
 
+/// A page in the navbar
+/// The title falls back to the file name, pages without a `navbar_order` go last
 #[derive(Debug)]
-pub struct PageInfo<'a> {
+pub struct PageInfo {
     pub path: String,
-    pub title: &'a String,
-    pub order: i32,
+    pub title: String,
+    pub order: Option<i32>,
     pub is_current: bool,
 }
 
@@ -36,6 +41,9 @@ pub struct PageInfo<'a> {
 pub struct Navbar {
     pub values: HashMap<String, String>,
     pub path: String,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
     pub id: u32,
 }
 
@@ -71,54 +79,32 @@ impl Navbar {
         output_files
     }
 
-    fn get_info<'a>(&self, pages: &[&'a Parser]) -> Vec<PageInfo<'a>> {
-        let mut info: Vec<PageInfo<'a>> = vec![];
-        for page in pages {
-            let frontmatter = &page.metadata.frontmatter;
-
-            let order = frontmatter
-                .get_value("navbar_order")
-                .unwrap()
-                .parse::<i32>()
-                .unwrap();
-
-            let is_current = self
-                .values
-                .get("navbar_order")
-                .unwrap()
-                .parse::<i32>()
-                .unwrap()
-                != order;
-
-            let title = frontmatter.get_value("navbar_title").unwrap_or(
-                frontmatter
-                    .get_value("title")
-                    .unwrap_or(frontmatter.get_file_name().unwrap()),
-            );
-
-            let mut path = frontmatter.get_path().unwrap().clone();
-            if path == String::from("/") {
-                path = String::new();
-            }
-            let path = format!("{}/{}.html", path, frontmatter.get_file_name().unwrap());
-            info.push(PageInfo {
-                title,
-                path,
-                order,
-                is_current,
-            });
-        }
-        info.sort_by(|a, b| a.order.cmp(&b.order));
+    fn get_info(&self, pages: &[&Parser], warnings: &Warnings) -> Vec<PageInfo> {
+        let mut info = pages
+            .iter()
+            .map(|page| {
+                let path = self.urls.path(page);
+                PageInfo {
+                    title: page::title(page, &["navbar_title", "title"]),
+                    order: page::order(warnings, page, "navbar_order", NAME),
+                    is_current: path == self.url,
+                    path,
+                }
+            })
+            .collect::<Vec<PageInfo>>();
+        info.sort_by(|a, b| {
+            page::cmp_missing_last(a.order, b.order, false).then_with(|| a.path.cmp(&b.path))
+        });
         info
     }
 
-    fn gen_elements<'a>(&self, pages: &'a [PageInfo<'a>]) -> Vec<Element> {
+    fn gen_elements(&self, pages: &[PageInfo]) -> Vec<Element> {
         let customs = pages
             .iter()
             .map(|page| {
                 let title = page.title.clone();
-                let url = page.path.clone();
-                let enabled = page.is_current.clone().to_string();
+                let url = self.urls.url(&page.path, &self.url);
+                let enabled = (!page.is_current).to_string();
 
                 let mut custom = CustomElm::new();
                 custom.name = String::from(ITEM_NAME);
@@ -136,16 +122,23 @@ impl Navbar {
 impl Plugin for Navbar {
     fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
         self.values = page.metadata.frontmatter.get_values().unwrap_or_default();
-        self.path = page.metadata.frontmatter.get_path().cloned().unwrap();
+        self.path = page
+            .metadata
+            .frontmatter
+            .get_path()
+            .cloned()
+            .unwrap_or_default();
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
         self.id = custom.id;
     }
 
-    fn run(&mut self, target: &mut Parser, pages: &[Option<&Parser>]) {
+    fn run(&mut self, target: &mut Parser, pages: &[Option<&Parser>], warnings: &Warnings) {
         let page = target.clone();
         let mod_element = target.ast.find_mut(self.id).unwrap();
 
         let pages = self.get_pages(&page, pages);
-        let infos = self.get_info(&pages);
+        let infos = self.get_info(&pages, warnings);
         let mut elements = self.gen_elements(&infos);
 
         let mut wrapper = CustomElm::new();
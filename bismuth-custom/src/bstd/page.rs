@@ -0,0 +1,121 @@
+use bismuth_error::warning::{Warning, Warnings};
+use bismuth_md::MarkdownFile;
+use bismuth_parser::{date::parse_date, Parser};
+use chrono::NaiveDateTime;
+use serde_yaml::{Mapping, Value};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Gets the dir of the markdown file for a page, so paths in it can be relative to it
+/// Ie `/blogs/post.md` -> `{src}/blogs`
+pub fn dir(file: &Parser, src: &Path) -> PathBuf {
+    let path = file.metadata.absolute_path.to_string_lossy();
+    let path = src.join(path.trim_start_matches(['/', '\\']));
+    path.parent().map(Path::to_path_buf).unwrap_or(path)
+}
+
+/// Makes a generated page, that only has the element with `values`
+/// The frontmatter and values are written as yaml, so titles and terms with `"`, `:` or `#` are kept as they are
+pub fn generate(
+    path: String,
+    frontmatter: &[(&str, &str)],
+    values: &[(&str, &str)],
+) -> MarkdownFile {
+    let yaml = |pairs: &[(&str, &str)]| {
+        let map = pairs
+            .iter()
+            .map(|(k, v)| (Value::from(*k), Value::from(*v)))
+            .collect::<Mapping>();
+        serde_yaml::to_string(&map).expect("Strings should always be valid yaml")
+    };
+    MarkdownFile {
+        path: PathBuf::from(path),
+        content: format!(
+            "---\n{}---\n%{{{{\n{}}}}}\n",
+            yaml(frontmatter),
+            yaml(values)
+        ),
+    }
+}
+
+/// Reports a warning for `file`, found by `element`
+pub fn report(warnings: &Warnings, file: &Parser, element: &str, message: String) {
+    warnings.warn(Warning::new(
+        file.metadata.absolute_path.clone(),
+        Some(element),
+        message,
+    ));
+}
+
+/// Reports an error for `file`, found by `element` on `line`, these stop the build
+pub fn fail(warnings: &Warnings, file: &Parser, element: &str, line: usize, message: String) {
+    warnings.error(
+        Warning::new(file.metadata.absolute_path.clone(), Some(element), message).with_line(line),
+    );
+}
+
+/// Gets the title of a page from the first value in `keys` that is set, then the `title` in the frontmatter
+/// Falls back to the file name, pages do not need a title so this is not a warning
+pub fn title(file: &Parser, keys: &[&str]) -> String {
+    let frontmatter = &file.metadata.frontmatter;
+    if let Some(title) = keys.iter().find_map(|key| frontmatter.get_value(key)) {
+        return title.clone();
+    }
+    // the frontmatter title defaults to the file name
+    frontmatter
+        .get_title()
+        .or(frontmatter.get_file_name())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Compares two values where pages that are missing one go last, even when `descending`
+pub fn cmp_missing_last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Gets the `date` value of a page, and the date it was parsed to
+/// Falls back to when the file was last modified
+pub fn date(warnings: &Warnings, file: &Parser, element: &str) -> (String, Option<NaiveDateTime>) {
+    if let Some(date) = file.metadata.frontmatter.get_value("date") {
+        return (date.clone(), parse_date(date));
+    }
+
+    match file.metadata.modified {
+        Some(modified) => {
+            let date = modified.format("%Y-%m-%d").to_string();
+            report(
+                warnings,
+                file,
+                element,
+                format!("missing `date`, using the last modified date ({date})"),
+            );
+            (date, Some(modified))
+        }
+        None => {
+            report(warnings, file, element, String::from("missing `date`"));
+            (String::new(), None)
+        }
+    }
+}
+
+/// Gets a number used to order pages
+/// Pages without one should be put last
+pub fn order(warnings: &Warnings, file: &Parser, key: &str, element: &str) -> Option<i32> {
+    let value = file.metadata.frontmatter.get_value(key);
+    let order = value.and_then(|o| o.trim().parse::<i32>().ok());
+    if order.is_none() {
+        let message = match value {
+            Some(value) => format!("`{key}` is not a number ({value}), putting it last"),
+            None => format!("missing `{key}`, putting it last"),
+        };
+        report(warnings, file, element, message);
+    }
+    order
+}
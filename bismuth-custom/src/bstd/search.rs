@@ -0,0 +1,90 @@
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    custom::CustomElm,
+    tree::{Element, Kind},
+    Parser,
+};
+
+use crate::plugin::Plugin;
+
+pub const NAME: &str = "search";
+
+pub const WRAPPER_NAME: &str = "builtin_search_wrapper";
+pub const WRAPPER: &str = include_str!("../../data/search.html");
+
+/// A search box, the results are found with the search index made by the build
+#[derive(Debug, Default)]
+pub struct Search {
+    pub placeholder: String,
+    /// The url of the search index
+    pub index: String,
+    pub id: u32,
+}
+
+impl Plugin for Search {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        self.placeholder = custom
+            .data
+            .get("placeholder")
+            .cloned()
+            .unwrap_or_else(|| String::from("Search..."));
+        self.index = custom.data.get("index").cloned().unwrap_or_else(|| {
+            let file = match custom.config.search_index.is_empty() {
+                true => "search-index.json",
+                false => custom.config.search_index.trim_start_matches('/'),
+            };
+            let urls = &custom.config.urls;
+            urls.url(&format!("/{file}"), &urls.path(page))
+        });
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, _files: &[Option<&Parser>], _: &Warnings) {
+        let mut wrapper = CustomElm::new();
+        wrapper.name = WRAPPER_NAME.to_string();
+        // both are put in attributes
+        for (key, value) in [("placeholder", &self.placeholder), ("index", &self.index)] {
+            wrapper.values.insert(
+                String::from(key),
+                html_escape::encode_double_quoted_attribute(value).to_string(),
+            );
+        }
+
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element
+            .elements
+            .push(Element::new(Kind::CustomElement(wrapper)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CustomConfig;
+    use crate::testing::{customs, page, run, values};
+
+    #[test]
+    fn search_test() {
+        let parser = page(
+            "/blogs/post.md",
+            "%{{\nname: search\n}}\n\n%{{\nname: search\nplaceholder: Find a \"post\"\nindex: /index.json\n}}\n",
+        );
+        let config = CustomConfig {
+            search_index: String::from("search/index.json"),
+            ..Default::default()
+        };
+        let parser = run(parser, &[], &config, &Warnings::default());
+
+        let boxes = customs(&parser.ast.elements, WRAPPER_NAME)
+            .into_iter()
+            .map(|e| format!("{} {}", values(e)["placeholder"], values(e)["index"]))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            boxes,
+            [
+                "Search... /search/index.json",
+                "Find a &quot;post&quot; /index.json"
+            ]
+        );
+    }
+}
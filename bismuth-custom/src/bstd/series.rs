@@ -0,0 +1,264 @@
+use bismuth_error::warning::Warnings;
+use bismuth_md::MarkdownFile;
+use bismuth_parser::{
+    custom::CustomElm,
+    slug::slugify,
+    tree::{Element, Kind},
+    url::Urls,
+    Parser,
+};
+
+use crate::bstd::page;
+use crate::plugin::Plugin;
+
+pub const NAME: &str = "series";
+
+/// The template kind of the generated pages
+pub const KIND: &str = "taxonomy";
+
+pub const ITEM_NAME: &str = "builtin_series_item";
+pub const ITEM: &str = include_str!("../../data/series_item.html");
+
+pub const WRAPPER_NAME: &str = "builtin_series_wrapper";
+pub const WRAPPER: &str = include_str!("../../data/series_wrapper.html");
+
+/// Ie `/series/making-a-blog.md`
+fn index_file(series: &str) -> String {
+    format!("/series/{}.md", slugify(series))
+}
+
+/// Ie `/series/making-a-blog.html`
+pub fn index_url(urls: &Urls, series: &str) -> String {
+    urls.file(&index_file(series), KIND)
+}
+
+/// Makes a index page for each series
+/// Ie `/series/making-a-blog.html`
+pub fn gen_pages(files: &[&Parser]) -> Vec<MarkdownFile> {
+    let mut series: Vec<&String> = files
+        .iter()
+        .filter_map(|f| f.metadata.frontmatter.get_series())
+        .collect();
+    series.sort_by_key(|s| slugify(s));
+    series.dedup_by_key(|s| slugify(s));
+
+    series
+        .iter()
+        .map(|name| {
+            page::generate(
+                index_file(name),
+                &[("title", name), ("kind", KIND)],
+                &[("name", NAME), ("series", name)],
+            )
+        })
+        .collect()
+}
+
+/// A part of a series, pages without a `series_order` go last
+#[derive(Debug)]
+pub struct Part {
+    pub url: String,
+    pub title: String,
+    pub order: Option<i32>,
+}
+
+impl Part {
+    fn new(file: &Parser, urls: &Urls, warnings: &Warnings) -> Self {
+        let order = file.metadata.frontmatter.get_series_order();
+        if order.is_none() {
+            page::report(
+                warnings,
+                file,
+                NAME,
+                String::from("missing `series_order`, putting it last"),
+            );
+        }
+        Part {
+            url: urls.path(file),
+            title: page::title(file, &["title"]),
+            order,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Series {
+    pub series: Option<String>,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
+    pub id: u32,
+}
+
+impl Series {
+    fn get_parts(
+        &self,
+        target: &Parser,
+        files: &[Option<&Parser>],
+        series: &str,
+        warnings: &Warnings,
+    ) -> Vec<Part> {
+        let slug = slugify(series);
+        let mut parts = files
+            .iter()
+            .flatten()
+            .copied()
+            .chain([target])
+            .filter(|f| {
+                f.metadata
+                    .frontmatter
+                    .get_series()
+                    .map(|s| slugify(s) == slug)
+                    .unwrap_or_default()
+            })
+            .map(|f| Part::new(f, &self.urls, warnings))
+            .collect::<Vec<Part>>();
+
+        parts.sort_by(|a, b| {
+            page::cmp_missing_last(a.order, b.order, false).then_with(|| a.url.cmp(&b.url))
+        });
+        parts
+    }
+
+    fn gen_wrapper(&self, series: &str, parts: &[Part]) -> Element {
+        let current = parts.iter().position(|p| p.url == self.url);
+        let prev = current
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| parts.get(i));
+        let next = current.and_then(|i| parts.get(i + 1));
+        let link = |part: Option<&Part>| {
+            part.map(|p| self.urls.url(&p.url, &self.url))
+                .unwrap_or_default()
+        };
+
+        let mut wrapper = CustomElm::new();
+        wrapper.name = WRAPPER_NAME.to_string();
+        let values = [
+            ("series", series.to_string()),
+            (
+                "index",
+                self.urls.url(&index_url(&self.urls, series), &self.url),
+            ),
+            ("has_prev", prev.is_some().to_string()),
+            ("has_next", next.is_some().to_string()),
+            ("prev", link(prev)),
+            (
+                "prev_title",
+                prev.map(|p| p.title.clone()).unwrap_or_default(),
+            ),
+            ("next", link(next)),
+            (
+                "next_title",
+                next.map(|p| p.title.clone()).unwrap_or_default(),
+            ),
+        ];
+        for (key, value) in values {
+            wrapper.values.insert(String::from(key), value);
+        }
+
+        let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
+        for part in parts {
+            let mut custom = CustomElm::new();
+            custom.name = String::from(ITEM_NAME);
+            custom
+                .values
+                .insert(String::from("title"), part.title.clone());
+            custom
+                .values
+                .insert(String::from("url"), self.urls.url(&part.url, &self.url));
+            custom
+                .values
+                .insert(String::from("current"), (part.url == self.url).to_string());
+            wrapper_elm
+                .elements
+                .push(Element::new(Kind::CustomElement(custom)));
+        }
+        wrapper_elm
+    }
+}
+
+impl Plugin for Series {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        // the index pages say which series to show, posts use their own
+        self.series = custom
+            .data
+            .get("series")
+            .or(page.metadata.frontmatter.get_series())
+            .cloned();
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, files: &[Option<&Parser>], warnings: &Warnings) {
+        let series = match &self.series {
+            Some(s) => s.clone(),
+            None => {
+                page::report(
+                    warnings,
+                    target,
+                    NAME,
+                    String::from("missing `series`, the series will be empty"),
+                );
+                return;
+            }
+        };
+
+        let parts = self.get_parts(target, files, &series, warnings);
+        let wrapper = self.gen_wrapper(&series, &parts);
+
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element.elements.push(wrapper);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CustomConfig;
+    use crate::testing::{customs, page, run, values};
+
+    #[test]
+    fn series_test() {
+        let parts = [
+            ("/blog/two.md", "Part two", "2"),
+            ("/blog/one.md", "Part one", "1"),
+            ("/blog/three.md", "Part three", "3"),
+        ]
+        .iter()
+        .map(|(path, title, order)| {
+            page(
+                path,
+                &format!("---\ntitle: {title}\nseries: Making a blog\nseries_order: {order}\n---\n%{{{{\nname: series\n}}}}"),
+            )
+        })
+        .collect::<Vec<Parser>>();
+
+        let parser = run(
+            parts[0].clone(),
+            &[&parts[1], &parts[2]],
+            &CustomConfig::default(),
+            &Warnings::default(),
+        );
+        let wrapper = customs(&parser.ast.elements, WRAPPER_NAME)[0];
+        assert_eq!(values(wrapper)["index"], "/series/making-a-blog.html");
+        assert_eq!(values(wrapper)["prev"], "/blog/one.html");
+        assert_eq!(values(wrapper)["next_title"], "Part three");
+
+        let items = customs(&wrapper.elements, ITEM_NAME)
+            .into_iter()
+            .map(|item| format!("{} {}", values(item)["title"], values(item)["current"]))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            items,
+            ["Part one false", "Part two true", "Part three false"]
+        );
+
+        let pages = gen_pages(&parts.iter().collect::<Vec<&Parser>>());
+        assert_eq!(pages.len(), 1);
+        assert_eq!(
+            pages[0].path,
+            std::path::PathBuf::from("/series/making-a-blog.md")
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use crate::bstd::page;
+use crate::config::CustomConfig;
+use crate::plugin::Plugin;
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    fence::parse_ranges,
+    tree::{Element, Kind},
+    Parser,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const NAME: &str = "snippet";
+
+const REGION_START: &str = "ANCHOR:";
+const REGION_END: &str = "ANCHOR_END:";
+
+fn is_marker(line: &str) -> bool {
+    line.contains(REGION_START) || line.contains(REGION_END)
+}
+
+/// Checks if `line` is the marker for `region`, ie. `// ANCHOR: main`
+fn marks(line: &str, marker: &str, region: &str) -> bool {
+    line.split_once(marker)
+        .map(|(_, name)| name.split_whitespace().next() == Some(region))
+        .unwrap_or_default()
+}
+
+/// Removes the indent that all the lines have
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or_default();
+    lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or_default().trim_end())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Gets part of a file, either by `lines` (ie. `10-30` or `1-3,7`) or by a `region`
+/// A region is between `ANCHOR: name` and `ANCHOR_END: name`, in a comment in the file
+/// The lines with region markers are not included
+pub fn select(content: &str, lines: Option<&str>, region: Option<&str>) -> Result<String, String> {
+    let all = content.lines().collect::<Vec<&str>>();
+
+    let selected = match (lines, region) {
+        (Some(lines), _) => {
+            let ranges = parse_ranges(lines);
+            if ranges.is_empty() {
+                return Err(format!("`{lines}` is not a valid line range"));
+            }
+            let mut selected = vec![];
+            for (start, end) in ranges {
+                if start == 0 || end > all.len() {
+                    return Err(format!(
+                        "lines `{start}-{end}` are out of range, the file has {} lines",
+                        all.len()
+                    ));
+                }
+                selected.extend_from_slice(&all[start - 1..end]);
+            }
+            selected
+        }
+        (None, Some(region)) => {
+            let start = all
+                .iter()
+                .position(|l| marks(l, REGION_START, region))
+                .ok_or_else(|| format!("the region `{region}` does not exist"))?;
+            let end = all[start..]
+                .iter()
+                .position(|l| marks(l, REGION_END, region))
+                .map(|e| start + e)
+                .ok_or_else(|| format!("the region `{region}` has no `{REGION_END} {region}`"))?;
+            all[start + 1..end].to_vec()
+        }
+        (None, None) => all,
+    };
+
+    let selected = selected
+        .into_iter()
+        .filter(|l| !is_marker(l))
+        .collect::<Vec<&str>>();
+    Ok(dedent(&selected))
+}
+
+/// The lang to highlight a file as, from its extension
+pub fn lang(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("plaintext"))
+}
+
+/// Makes a code block, the same as one from a ``` fence
+pub fn code_element(code: &str, lang: &str, values: &HashMap<String, String>) -> Element {
+    let mut element = Element::new(Kind::BlockCode);
+    element.add_attr(
+        "lang",
+        &values.get("lang").map(String::as_str).unwrap_or(lang),
+    );
+    for key in ["title", "highlight"] {
+        if let Some(value) = values.get(key) {
+            element.add_attr(key, value);
+        }
+    }
+    if values
+        .get("linenos")
+        .map(|l| l == "true")
+        .unwrap_or_default()
+    {
+        element.add_attr("linenos", &true);
+    }
+    // the first new line is taken off when rendering, as it is the one after the fence
+    element.text = Some(format!("\n{code}"));
+    element
+}
+
+/// Where a snippet is from, ie. `snippets/{project}/{state}/{file}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetRef {
+    /// The page that has the snippet
+    pub page: PathBuf,
+    pub line: usize,
+    pub project: String,
+    pub state: String,
+    pub file: String,
+}
+
+impl SnippetRef {
+    fn from_values(page: &Parser, line: usize, values: &HashMap<String, String>) -> Option<Self> {
+        Some(SnippetRef {
+            page: page.metadata.absolute_path.clone(),
+            line,
+            project: values.get("project")?.clone(),
+            state: values.get("state")?.clone(),
+            file: values.get("file")?.clone(),
+        })
+    }
+
+    /// The dir of the state
+    pub fn dir(&self, config: &CustomConfig) -> PathBuf {
+        config
+            .project
+            .join(&config.snippets.dir)
+            .join(&self.project)
+            .join(&self.state)
+    }
+}
+
+fn find_refs(page: &Parser, elements: &[Element], refs: &mut Vec<SnippetRef>) {
+    for element in elements {
+        if let Kind::CustomElement(c) = &element.kind {
+            if c.name == NAME {
+                refs.extend(SnippetRef::from_values(page, c.line, &c.values));
+            }
+        }
+        find_refs(page, &element.elements, refs);
+    }
+}
+
+/// Finds all the snippets in the files, so they can be checked
+pub fn find(files: &[&Parser]) -> Vec<SnippetRef> {
+    let mut refs = vec![];
+    for file in files {
+        find_refs(file, &file.ast.elements, &mut refs);
+    }
+    refs
+}
+
+/// Takes a snippet from a state of a code base
+/// `project`, `state` and `file` are needed. Then either `lines` or `region`, if there are neither the whole file is used
+/// `title`, `highlight`, `linenos` and `lang` are the same as the options for a ``` fence
+#[derive(Debug, Default)]
+pub struct Snippet {
+    id: u32,
+    line: usize,
+    values: HashMap<String, String>,
+    dir: Option<PathBuf>,
+    project: PathBuf,
+}
+
+impl Plugin for Snippet {
+    fn pre_load(&mut self, target: &Parser, custom: &crate::Custom) {
+        self.id = custom.id;
+        self.line = custom.line;
+        self.values = custom.data.clone();
+        self.dir = SnippetRef::from_values(target, custom.line, &custom.data)
+            .map(|r| r.dir(custom.config));
+        self.project = custom.config.project.clone();
+    }
+
+    fn run(&mut self, target: &mut Parser, _files: &[Option<&Parser>], warnings: &Warnings) {
+        let (dir, file) = match (&self.dir, self.values.get("file")) {
+            (Some(dir), Some(file)) => (dir, file),
+            _ => {
+                let message = String::from("`project`, `state` and `file` are needed");
+                return page::fail(warnings, target, NAME, self.line, message);
+            }
+        };
+        let path = match in_project(&dir.join(file), &self.project) {
+            Ok(p) => p,
+            Err(e) => return page::fail(warnings, target, NAME, self.line, e),
+        };
+        insert(
+            warnings,
+            target,
+            NAME,
+            self.id,
+            self.line,
+            &path,
+            &self.values,
+        );
+    }
+}
+
+/// Makes sure `path` is in the project, so a page can not show any file on the disk (ie. `../../../.ssh/id_rsa`)
+/// Gives back the canonical path
+pub fn in_project(path: &Path, project: &Path) -> Result<PathBuf, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("could not read `{}`: {e}", path.to_string_lossy()))?;
+    let project = project
+        .canonicalize()
+        .unwrap_or_else(|_| project.to_path_buf());
+    match canonical.starts_with(&project) {
+        true => Ok(canonical),
+        false => Err(format!(
+            "`{}` is not in the project",
+            path.to_string_lossy()
+        )),
+    }
+}
+
+/// Reads `path`, and adds the `lines` or `region` of it as a code block to the element with `id`
+/// The build fails if the file, lines or region do not exist
+pub fn insert(
+    warnings: &Warnings,
+    target: &mut Parser,
+    element: &str,
+    id: u32,
+    line: usize,
+    path: &Path,
+    values: &HashMap<String, String>,
+) {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            let message = format!("could not read `{}`: {e}", path.to_string_lossy());
+            return page::fail(warnings, target, element, line, message);
+        }
+    };
+    let code = select(
+        &content,
+        values.get("lines").map(String::as_str),
+        values.get("region").map(String::as_str),
+    );
+    let code = match code {
+        Ok(c) => c,
+        Err(e) => {
+            let message = format!("`{}`: {e}", path.to_string_lossy());
+            return page::fail(warnings, target, element, line, message);
+        }
+    };
+
+    let mut values = values.clone();
+    if let Some(file) = values.get("file").cloned() {
+        values.entry(String::from("title")).or_insert(file);
+    }
+    let code_element = code_element(&code, &lang(path), &values);
+    let mod_element = target.ast.find_mut(id).unwrap();
+    mod_element.elements.push(code_element);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{errors, page, run};
+    use bismuth_test::project;
+
+    const FILE: &str = "use std::io;
+
+fn main() {
+    // ANCHOR: input
+    let mut guess = String::new();
+    io::stdin().read_line(&mut guess).unwrap();
+    // ANCHOR_END: input
+}
+";
+
+    #[test]
+    fn select_lines() {
+        assert_eq!(select(FILE, Some("3"), None).unwrap(), "fn main() {");
+        assert_eq!(select(FILE, Some("1,8"), None).unwrap(), "use std::io;\n}");
+        assert!(select(FILE, Some("7-20"), None).is_err());
+        assert!(select(FILE, Some("main"), None).is_err());
+    }
+
+    #[test]
+    fn select_region() {
+        assert_eq!(
+            select(FILE, None, Some("input")).unwrap(),
+            "let mut guess = String::new();\nio::stdin().read_line(&mut guess).unwrap();"
+        );
+        assert!(select(FILE, None, Some("output")).is_err());
+        // a region has to be closed
+        let open = "// ANCHOR: main\nfn main() {}\n";
+        assert!(select(open, None, Some("main")).is_err());
+        // markers are never shown
+        assert!(!select(FILE, None, None).unwrap().contains("ANCHOR"));
+    }
+
+    #[test]
+    fn in_project_only() {
+        let project = project(&[("snippets/game/step-1/main.rs", FILE)]);
+        let snippets = project.path().join("snippets/game/step-1");
+
+        assert!(in_project(&snippets.join("main.rs"), project.path()).is_ok());
+        assert!(in_project(&snippets.join("other.rs"), project.path()).is_err());
+        let outside = snippets
+            .join("../../../..")
+            .join(project.path().file_name().unwrap());
+        assert!(in_project(&outside, &snippets).is_err());
+    }
+
+    #[test]
+    fn snippet_test() {
+        let warnings = Warnings::default();
+        let project = project(&[(
+            "snippets/game/step-1/src/main.rs",
+            "fn main() {\n    // ANCHOR: hello\n    println!(\"hello\");\n    // ANCHOR_END: hello\n}\n",
+        )]);
+        let config = CustomConfig {
+            project: project.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let content = "%{{\nname: snippet\nproject: game\nstate: step-1\nfile: src/main.rs\nregion: hello\n}}\n\n%{{\nname: snippet\nproject: game\nstate: step-2\nfile: src/main.rs\n}}\n";
+        let parser = run(page("/snippet.md", content), &[], &config, &warnings);
+
+        let code = parser
+            .ast
+            .elements
+            .iter()
+            .flat_map(|e| &e.elements)
+            .find(|e| e.kind == Kind::BlockCode)
+            .unwrap();
+        assert_eq!(code.text.as_deref(), Some("\nprintln!(\"hello\");"));
+        assert_eq!(code.get_attr("lang").unwrap(), "rs");
+        assert_eq!(code.get_attr("title").unwrap(), "src/main.rs");
+
+        let errors = errors(&warnings);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("/snippet.md:9 (snippet): could not read"));
+    }
+}
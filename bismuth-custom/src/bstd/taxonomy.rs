@@ -0,0 +1,321 @@
+use bismuth_error::warning::{Warning, Warnings};
+use bismuth_md::MarkdownFile;
+use bismuth_parser::{
+    custom::CustomElm,
+    slug::slugify,
+    tree::{Element, Kind},
+    url::Urls,
+    Parser,
+};
+
+use crate::bstd::bloglist::{self, BlogList, ListOptions, Post, Sort};
+use crate::bstd::page;
+use crate::plugin::Plugin;
+
+/// Lists the pages for a term, or all the terms if no term is given
+pub const NAME: &str = "taxonomy";
+/// Lists the tags of the current page
+pub const TAGS_NAME: &str = "tags";
+
+/// The template kind of the generated pages
+pub const KIND: &str = "taxonomy";
+
+pub const TAGS: &str = "tags";
+pub const CATEGORIES: &str = "categories";
+pub const TAXONOMIES: &[&str] = &[TAGS, CATEGORIES];
+
+pub const TAG_ITEM_NAME: &str = "builtin_tag_item";
+pub const TAG_ITEM: &str = include_str!("../../data/tag_item.html");
+
+pub const TAG_WRAPPER_NAME: &str = "builtin_tag_wrapper";
+pub const TAG_WRAPPER: &str = include_str!("../../data/tag_wrapper.html");
+
+pub const TERM_ITEM_NAME: &str = "builtin_term_item";
+pub const TERM_ITEM: &str = include_str!("../../data/term_item.html");
+
+pub const TERM_WRAPPER_NAME: &str = "builtin_term_wrapper";
+pub const TERM_WRAPPER: &str = include_str!("../../data/term_wrapper.html");
+
+#[derive(Debug, PartialEq)]
+pub struct Term {
+    pub name: String,
+    pub slug: String,
+    pub count: usize,
+}
+
+pub fn get_terms<'a>(file: &'a Parser, taxonomy: &str) -> Option<&'a Vec<String>> {
+    let frontmatter = &file.metadata.frontmatter;
+    match taxonomy {
+        TAGS => frontmatter.get_tags(),
+        CATEGORIES => frontmatter.get_categories(),
+        _ => None,
+    }
+}
+
+/// Ie `/tags/rust.md`
+fn term_file(taxonomy: &str, term: &str) -> String {
+    format!("/{taxonomy}/{}.md", slugify(term))
+}
+
+/// Ie `/tags/rust.html`
+pub fn term_url(urls: &Urls, taxonomy: &str, term: &str) -> String {
+    urls.file(&term_file(taxonomy, term), KIND)
+}
+
+/// A term can not be called `index`, as its page would be the page with all the terms
+fn is_reserved(slug: &str) -> bool {
+    slug == "index"
+}
+
+/// Gets all the terms used in a taxonomy, sorted by their slug
+/// Terms with the same slug (ie `Rust` and `rust`) are merged, the first name found is used
+pub fn collect_terms(files: &[&Parser], taxonomy: &str) -> Vec<Term> {
+    let mut terms: Vec<Term> = vec![];
+    for file in files {
+        // so a page can only count once for each term
+        let mut seen: Vec<String> = vec![];
+        for name in get_terms(file, taxonomy).cloned().unwrap_or_default() {
+            let slug = slugify(&name);
+            if slug.is_empty() || is_reserved(&slug) || seen.contains(&slug) {
+                continue;
+            }
+            seen.push(slug.clone());
+
+            match terms.iter_mut().find(|t| t.slug == slug) {
+                Some(term) => term.count += 1,
+                None => terms.push(Term {
+                    name,
+                    slug,
+                    count: 1,
+                }),
+            }
+        }
+    }
+    terms.sort_by(|a, b| a.slug.cmp(&b.slug));
+    terms
+}
+
+fn gen_page(path: String, title: &str, taxonomy: &str, term: Option<&str>) -> MarkdownFile {
+    let mut values = vec![("name", NAME), ("taxonomy", taxonomy)];
+    values.extend(term.map(|t| ("term", t)));
+    page::generate(path, &[("title", title), ("kind", KIND)], &values)
+}
+
+/// Fails the build for the pages that use a term called `index`
+fn check_reserved(files: &[&Parser], taxonomy: &str, warnings: &Warnings) {
+    for file in files {
+        let terms = get_terms(file, taxonomy).cloned().unwrap_or_default();
+        for name in terms.iter().filter(|t| is_reserved(&slugify(t))) {
+            warnings.error(Warning::new(
+                file.metadata.absolute_path.clone(),
+                Some(taxonomy),
+                format!("`{name}` can not be used as one of the {taxonomy}, `/{taxonomy}/index` lists all of them"),
+            ));
+        }
+    }
+}
+
+/// Makes a page for each term, and a index page for each taxonomy that is used
+/// Ie `/tags/index.html` and `/tags/rust.html`
+pub fn gen_pages(files: &[&Parser], warnings: &Warnings) -> Vec<MarkdownFile> {
+    let mut pages = vec![];
+    for taxonomy in TAXONOMIES {
+        check_reserved(files, taxonomy, warnings);
+        let terms = collect_terms(files, taxonomy);
+        if terms.is_empty() {
+            continue;
+        }
+
+        let mut title = taxonomy.to_string();
+        title[..1].make_ascii_uppercase();
+        pages.push(gen_page(
+            format!("/{taxonomy}/index.md"),
+            &title,
+            taxonomy,
+            None,
+        ));
+
+        for term in terms {
+            pages.push(gen_page(
+                term_file(taxonomy, &term.name),
+                &term.name,
+                taxonomy,
+                Some(&term.name),
+            ));
+        }
+    }
+    pages
+}
+
+fn wrap(name: &str, mut elements: Vec<Element>) -> Element {
+    let mut wrapper = CustomElm::new();
+    wrapper.name = name.to_string();
+    let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
+    wrapper_elm.elements.append(&mut elements);
+    wrapper_elm
+}
+
+#[derive(Debug, Default)]
+pub struct Taxonomy {
+    pub taxonomy: String,
+    pub term: Option<String>,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
+    pub id: u32,
+}
+
+impl Taxonomy {
+    fn get_posts<'a>(&self, files: &[&'a Parser], term: &str) -> Vec<&'a Parser> {
+        let slug = slugify(term);
+        files
+            .iter()
+            .filter(|file| {
+                get_terms(file, &self.taxonomy)
+                    .map(|terms| terms.iter().any(|t| slugify(t) == slug))
+                    .unwrap_or_default()
+            })
+            .copied()
+            .collect()
+    }
+
+    fn gen_terms(&self, files: &[&Parser]) -> Vec<Element> {
+        collect_terms(files, &self.taxonomy)
+            .iter()
+            .map(|term| {
+                let mut custom = CustomElm::new();
+                custom.name = String::from(TERM_ITEM_NAME);
+                custom
+                    .values
+                    .insert(String::from("title"), term.name.clone());
+                custom.values.insert(
+                    String::from("url"),
+                    self.urls
+                        .url(&term_url(&self.urls, &self.taxonomy, &term.name), &self.url),
+                );
+                custom
+                    .values
+                    .insert(String::from("count"), term.count.to_string());
+
+                Element::new(Kind::CustomElement(custom))
+            })
+            .collect::<Vec<Element>>()
+    }
+}
+
+impl Plugin for Taxonomy {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
+        self.taxonomy = custom
+            .data
+            .get("taxonomy")
+            .cloned()
+            .unwrap_or(String::from(TAGS));
+        self.term = custom.data.get("term").cloned();
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, files: &[Option<&Parser>], warnings: &Warnings) {
+        let files = files.iter().flatten().copied().collect::<Vec<&Parser>>();
+
+        let wrapper = match &self.term {
+            Some(term) => {
+                let mut posts = self
+                    .get_posts(&files, term)
+                    .into_iter()
+                    .map(|post| Post::new(post, &self.urls, NAME, Sort::Date, warnings))
+                    .collect::<Vec<Post>>();
+                let list = BlogList {
+                    options: ListOptions {
+                        descending: true,
+                        ..Default::default()
+                    },
+                    first_page: self.url.clone(),
+                    kind: String::from(KIND),
+                    urls: self.urls.clone(),
+                    ..Default::default()
+                };
+                list.sort_posts(&mut posts);
+                wrap(bloglist::WRAPPER_NAME, list.gen_templates(&posts))
+            }
+            None => wrap(TERM_WRAPPER_NAME, self.gen_terms(&files)),
+        };
+
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element.elements.push(wrapper);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Tags {
+    pub tags: Vec<String>,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
+    pub id: u32,
+}
+
+impl Plugin for Tags {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
+        self.tags = page
+            .metadata
+            .frontmatter
+            .get_tags()
+            .cloned()
+            .unwrap_or_default();
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, _: &[Option<&Parser>], _: &Warnings) {
+        let elements = self
+            .tags
+            .iter()
+            .map(|tag| {
+                let mut custom = CustomElm::new();
+                custom.name = String::from(TAG_ITEM_NAME);
+                custom.values.insert(String::from("title"), tag.clone());
+                custom.values.insert(
+                    String::from("url"),
+                    self.urls.url(&term_url(&self.urls, TAGS, tag), &self.url),
+                );
+
+                Element::new(Kind::CustomElement(custom))
+            })
+            .collect::<Vec<Element>>();
+
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element.elements.push(wrap(TAG_WRAPPER_NAME, elements));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{page, snapshot};
+
+    #[test]
+    fn gen_pages_test() {
+        let first = page(
+            "/blogs/first.md",
+            "---\ntags: [rust, Web Dev]\ncategories: [programming]\n---\n# First",
+        );
+        let second = page(
+            "/blogs/second.md",
+            "---\ntags: [Rust, rust, \"C++\", \"Rust: the \\\"good\\\" parts\"]\n---\n# Second",
+        );
+        let third = page("/blogs/third.md", "---\ntags: [Index]\n---\n# Third");
+
+        let warnings = Warnings::default();
+        let pages = gen_pages(&[&first, &second, &third], &warnings);
+        // `/tags/index` is the page with all the tags
+        assert_eq!(warnings.take_errors().len(), 1);
+        let pages = pages
+            .iter()
+            .map(|p| format!("{}:\n{}\n", p.path.to_string_lossy(), p.content))
+            .collect::<String>();
+        snapshot!(pages);
+    }
+}
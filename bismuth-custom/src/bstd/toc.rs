@@ -0,0 +1,128 @@
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    custom::CustomElm,
+    heading::element_text,
+    tree::{Element, Kind},
+    Parser,
+};
+
+use crate::plugin::Plugin;
+
+pub const NAME: &str = "toc";
+
+pub const WRAPPER_NAME: &str = "builtin_toc_wrapper";
+pub const WRAPPER: &str = include_str!("../../data/toc_wrapper.html");
+
+pub const LIST_NAME: &str = "builtin_toc_list";
+pub const LIST: &str = include_str!("../../data/toc_list.html");
+
+pub const ITEM_NAME: &str = "builtin_toc_item";
+pub const ITEM: &str = include_str!("../../data/toc_item.html");
+
+#[derive(Debug, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub id: String,
+    pub title: String,
+}
+
+/// Gets all the headers in the page between `min` and `max` levels
+/// Does not look inside custom elements
+pub fn get_headings(elements: &[Element], min: usize, max: usize) -> Vec<Heading> {
+    let mut headings = vec![];
+    for element in elements {
+        match &element.kind {
+            Kind::Header => {
+                let level = element
+                    .get_attr("level")
+                    .ok()
+                    .and_then(|l| l.parse::<usize>().ok())
+                    .unwrap_or(6);
+                if (min..=max).contains(&level) {
+                    headings.push(Heading {
+                        level,
+                        id: element.get_attr("id").cloned().unwrap_or_default(),
+                        title: element_text(element).trim().to_string(),
+                    });
+                }
+            }
+            Kind::CustomElement(_) => {}
+            _ => headings.append(&mut get_headings(&element.elements, min, max)),
+        }
+    }
+    headings
+}
+
+/// Nests the headings, so each heading has the smaller headings after it inside of it
+fn gen_elements(headings: &[Heading]) -> Vec<Element> {
+    let mut elements = vec![];
+    let mut index = 0;
+    while index < headings.len() {
+        let heading = &headings[index];
+        let end = headings[index + 1..]
+            .iter()
+            .position(|h| h.level <= heading.level)
+            .map(|p| index + 1 + p)
+            .unwrap_or(headings.len());
+
+        let mut custom = CustomElm::new();
+        custom.name = String::from(ITEM_NAME);
+        // the title is text, it is put into the html as is
+        custom.values.insert(
+            String::from("title"),
+            html_escape::encode_text(&heading.title).to_string(),
+        );
+        custom.values.insert(
+            String::from("id"),
+            html_escape::encode_double_quoted_attribute(&heading.id).to_string(),
+        );
+        let mut item = Element::new(Kind::CustomElement(custom));
+
+        let mut children = gen_elements(&headings[index + 1..end]);
+        if !children.is_empty() {
+            let mut list = CustomElm::new();
+            list.name = String::from(LIST_NAME);
+            let mut list = Element::new(Kind::CustomElement(list));
+            list.elements.append(&mut children);
+            item.elements.push(list);
+        }
+
+        elements.push(item);
+        index = end;
+    }
+    elements
+}
+
+#[derive(Debug, Default)]
+pub struct Toc {
+    pub min: usize,
+    pub max: usize,
+    pub id: u32,
+}
+
+impl Plugin for Toc {
+    fn pre_load(&mut self, _: &Parser, custom: &crate::Custom) {
+        let level = |key: &str, default: usize| {
+            custom
+                .data
+                .get(key)
+                .and_then(|l| l.trim().parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        self.min = level("min_level", 1);
+        self.max = level("max_level", 6);
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, _: &[Option<&Parser>], _: &Warnings) {
+        let headings = get_headings(&target.ast.elements, self.min, self.max);
+
+        let mut wrapper = CustomElm::new();
+        wrapper.name = WRAPPER_NAME.to_string();
+        let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
+        wrapper_elm.elements.append(&mut gen_elements(&headings));
+
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element.elements.push(wrapper_elm);
+    }
+}
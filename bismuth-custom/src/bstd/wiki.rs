@@ -0,0 +1,303 @@
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    custom::CustomElm,
+    slug::slugify,
+    tree::{Element, Kind},
+    url::{self, Urls},
+    Parser,
+};
+
+use crate::bstd::page;
+use crate::plugin::Plugin;
+
+/// Used for the errors of `[[page]]` links
+pub const LINK_NAME: &str = "wiki";
+
+pub const NAME: &str = "backlinks";
+
+pub const ITEM_NAME: &str = "builtin_backlinks_item";
+pub const ITEM: &str = include_str!("../../data/backlinks_item.html");
+
+pub const WRAPPER_NAME: &str = "builtin_backlinks_wrapper";
+pub const WRAPPER: &str = include_str!("../../data/backlinks_wrapper.html");
+
+/// What a `[[page]]` link can be to
+#[derive(Debug)]
+struct Page {
+    url: String,
+    /// Where the markdown file is, ie `/blogs/post.html`
+    path: String,
+    name: String,
+    title: Option<String>,
+}
+
+impl Page {
+    fn new(file: &Parser, urls: &Urls) -> Self {
+        let frontmatter = &file.metadata.frontmatter;
+        Page {
+            url: urls.path(file),
+            path: url::source(file),
+            name: frontmatter.get_file_name().cloned().unwrap_or_default(),
+            title: frontmatter.get_title().cloned(),
+        }
+    }
+
+    fn pages(target: &Parser, files: &[Option<&Parser>], urls: &Urls) -> Vec<Page> {
+        files
+            .iter()
+            .flatten()
+            .copied()
+            .chain([target])
+            .map(|file| Page::new(file, urls))
+            .collect()
+    }
+}
+
+/// Finds the page for a link, by its path (ie. `blogs/post`), file name, or title
+/// The url has the `#section` from the link, if there is one
+/// If more than one page matches in the same way, the link is ambiguous and is an error
+fn find(link: &str, pages: &[Page]) -> Result<String, String> {
+    let (name, section) = match link.split_once('#') {
+        Some((name, section)) => (name.trim(), Some(section.trim())),
+        None => (link.trim(), None),
+    };
+    let path = name
+        .trim_start_matches('/')
+        .trim_end_matches(".md")
+        .trim_end_matches(".html");
+    let by_path = |p: &&Page| p.path.trim_start_matches('/').trim_end_matches(".html") == path;
+    let by_name = |p: &&Page| p.name.eq_ignore_ascii_case(name) || p.name == slugify(name);
+    let by_title = |p: &&Page| {
+        p.title
+            .as_ref()
+            .map(|t| t.eq_ignore_ascii_case(name))
+            .unwrap_or_default()
+    };
+
+    let matches = [
+        pages.iter().filter(by_path).collect::<Vec<&Page>>(),
+        pages.iter().filter(by_name).collect(),
+        pages.iter().filter(by_title).collect(),
+    ];
+    let page = match matches.iter().find(|m| !m.is_empty()).map(Vec::as_slice) {
+        Some([page]) => page,
+        Some(candidates) => {
+            let candidates = candidates
+                .iter()
+                .map(|p| {
+                    format!(
+                        "`{}`",
+                        p.path.trim_start_matches('/').trim_end_matches(".html")
+                    )
+                })
+                .collect::<Vec<String>>();
+            return Err(format!(
+                "`[[{link}]]` could be more than one page ({})",
+                candidates.join(", ")
+            ));
+        }
+        None => return Err(format!("`[[{link}]]` is not a page")),
+    };
+    Ok(match section {
+        Some(section) => format!("{}#{}", page.url, slugify(section)),
+        None => page.url.clone(),
+    })
+}
+
+fn links(elements: &[Element], found: &mut Vec<String>) {
+    for element in elements {
+        if let (Kind::Link, Ok(link)) = (&element.kind, element.get_attr("wiki")) {
+            found.push(link.clone());
+        }
+        links(&element.elements, found);
+    }
+}
+
+fn resolve_elements(
+    elements: &mut [Element],
+    urls: &Urls,
+    from: &str,
+    pages: &[Page],
+    errors: &mut Vec<(usize, String)>,
+) {
+    for element in elements {
+        if let (Kind::Link, Ok(link)) = (&element.kind, element.get_attr("wiki")) {
+            match find(link, pages) {
+                Ok(url) => element.add_attr("link", &urls.url(&url, from)),
+                Err(message) => {
+                    let line = element
+                        .get_attr("line")
+                        .ok()
+                        .and_then(|l| l.parse().ok())
+                        .unwrap_or_default();
+                    errors.push((line, message));
+                }
+            }
+        }
+        resolve_elements(&mut element.elements, urls, from, pages, errors);
+    }
+}
+
+/// Finds the pages for the `[[page]]` links, and makes them into urls
+/// Links to pages that do not exist fail the build
+pub fn resolve(target: &mut Parser, files: &[Option<&Parser>], urls: &Urls, warnings: &Warnings) {
+    let pages = Page::pages(target, files, urls);
+    let from = urls.path(target);
+    let mut errors = vec![];
+    resolve_elements(&mut target.ast.elements, urls, &from, &pages, &mut errors);
+    for (line, message) in errors {
+        page::fail(warnings, target, LINK_NAME, line, message);
+    }
+}
+
+/// Lists the pages that link to the current page with `[[page]]` links
+#[derive(Debug, Default)]
+pub struct Backlinks {
+    pub title: String,
+    /// The url of the current page
+    pub url: String,
+    pub urls: Urls,
+    pub id: u32,
+}
+
+impl Backlinks {
+    fn gen_wrapper(&self, target: &Parser, files: &[Option<&Parser>]) -> Element {
+        let pages = Page::pages(target, files, &self.urls);
+        let mut backlinks = files
+            .iter()
+            .flatten()
+            .filter(|file| {
+                let mut found = vec![];
+                links(&file.ast.elements, &mut found);
+                found.iter().any(|link| {
+                    find(link, &pages)
+                        .map(|url| url.split('#').next() == Some(self.url.as_str()))
+                        .unwrap_or_default()
+                })
+            })
+            .map(|file| {
+                let url = self.urls.url(&self.urls.path(file), &self.url);
+                (url, page::title(file, &["title"]))
+            })
+            .collect::<Vec<(String, String)>>();
+        backlinks.sort();
+
+        let mut wrapper = CustomElm::new();
+        wrapper.name = WRAPPER_NAME.to_string();
+        wrapper
+            .values
+            .insert(String::from("title"), self.title.clone());
+
+        let mut wrapper_elm = Element::new(Kind::CustomElement(wrapper));
+        for (url, title) in backlinks {
+            let mut custom = CustomElm::new();
+            custom.name = String::from(ITEM_NAME);
+            custom.values.insert(String::from("title"), title);
+            custom.values.insert(String::from("url"), url);
+            wrapper_elm
+                .elements
+                .push(Element::new(Kind::CustomElement(custom)));
+        }
+        wrapper_elm
+    }
+}
+
+impl Plugin for Backlinks {
+    fn pre_load(&mut self, page: &Parser, custom: &crate::Custom) {
+        self.title = custom
+            .data
+            .get("title")
+            .cloned()
+            .unwrap_or_else(|| String::from("Linked from"));
+        self.urls = custom.config.urls.clone();
+        self.url = self.urls.path(page);
+        self.id = custom.id;
+    }
+
+    fn run(&mut self, target: &mut Parser, files: &[Option<&Parser>], _: &Warnings) {
+        let wrapper = self.gen_wrapper(target, files);
+        let mod_element = target.ast.find_mut(self.id).unwrap();
+        mod_element.elements.push(wrapper);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CustomConfig;
+    use crate::testing::{customs, errors, page, run, values};
+
+    fn links(elements: &[Element], found: &mut Vec<String>) {
+        for element in elements {
+            if element.kind == Kind::Link {
+                found.push(element.get_attr("link").cloned().unwrap_or_default());
+            }
+            links(&element.elements, found);
+        }
+    }
+
+    #[test]
+    fn wiki_test() {
+        let warnings = Warnings::default();
+        let pages = [
+            (
+                "/wiki/index.md",
+                "---\ntitle: Home\n---\nSee [[about]], [[Another Post|the post]], [[missing]], [[todo]] and [[notes/todo]]\n",
+            ),
+            (
+                "/blogs/another-post.md",
+                "---\ntitle: Another Post\n---\n[[Home#Intro]]\n\n%{{\nname: backlinks\n}}\n",
+            ),
+            ("/about.md", "---\ntitle: About me\n---\n# About\n"),
+            ("/wiki/todo.md", "# Todo\n"),
+            ("/notes/todo.md", "# Todo\n"),
+        ]
+        .iter()
+        .map(|(path, content)| page(path, content))
+        .collect::<Vec<Parser>>();
+
+        let config = CustomConfig::default();
+        let index = run(
+            pages[0].clone(),
+            &[&pages[1], &pages[2], &pages[3], &pages[4]],
+            &config,
+            &warnings,
+        );
+        let mut found = vec![];
+        links(&index.ast.elements, &mut found);
+        assert_eq!(
+            found,
+            [
+                "/about.html",
+                "/blogs/another-post.html",
+                "",
+                "",
+                "/notes/todo.html"
+            ]
+        );
+        assert_eq!(
+            errors(&warnings),
+            [
+                "/wiki/index.md:4 (wiki): `[[missing]]` is not a page",
+                "/wiki/index.md:4 (wiki): `[[todo]]` could be more than one page (`wiki/todo`, `notes/todo`)"
+            ]
+        );
+
+        let post = run(
+            pages[1].clone(),
+            &[&pages[0], &pages[2]],
+            &config,
+            &warnings,
+        );
+        let mut found = vec![];
+        links(&post.ast.elements, &mut found);
+        assert_eq!(found, ["/wiki/index.html#intro"]);
+
+        let wrapper = customs(&post.ast.elements, WRAPPER_NAME)[0];
+        let backlinks = customs(&wrapper.elements, ITEM_NAME)
+            .into_iter()
+            .map(|item| format!("{} {}", values(item)["title"], values(item)["url"]))
+            .collect::<Vec<String>>();
+        assert_eq!(backlinks, ["Home /wiki/index.html"]);
+    }
+}
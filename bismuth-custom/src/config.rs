@@ -0,0 +1,60 @@
+use bismuth_parser::url::Urls;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The parts of `bismuth.toml` that the customs use
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CustomConfig {
+    /// The name of the website, from `[website]`
+    pub name: String,
+    /// The project dir, the dirs in the config are relative to this
+    pub project: PathBuf,
+    /// The dir the markdown files are loaded from
+    pub src: PathBuf,
+    pub footer: FooterConfig,
+    pub snippets: SnippetsConfig,
+    /// The search index, relative to the output dir (ie. `search-index.json`)
+    pub search_index: String,
+    /// Makes the urls of the pages, and the links between them
+    pub urls: Urls,
+}
+
+/// The `[footer]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FooterConfig {
+    /// Adds the footer to every page
+    #[serde(default)]
+    pub enabled: bool,
+    /// Defaults to `© {year} {name}`
+    pub copyright: Option<String>,
+    #[serde(default)]
+    pub links: Vec<FooterLink>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FooterLink {
+    pub title: String,
+    pub url: String,
+}
+
+/// The `[snippets]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SnippetsConfig {
+    /// Where the code bases are, relative to the project dir
+    /// Each code base has a dir for each of its states, ie. `snippets/game/step-1/`
+    pub dir: String,
+    /// The commands used to check a code base, by its name
+    /// If a code base does not have one, it is based on its files (ie. `cargo build` if there is a `Cargo.toml`)
+    pub commands: BTreeMap<String, String>,
+}
+
+impl Default for SnippetsConfig {
+    fn default() -> Self {
+        Self {
+            dir: String::from("snippets"),
+            commands: BTreeMap::new(),
+        }
+    }
+}
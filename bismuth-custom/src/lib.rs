@@ -1,19 +1,28 @@
 use std::collections::HashMap;
 
+use bismuth_error::warning::Warnings;
+use bismuth_md::MarkdownFile;
 use bismuth_parser::{
     custom::CustomElm,
     tree::{Element, Kind},
+    url::Urls,
     Parser,
 };
 use bstd::navbar::add_navbar;
+use config::CustomConfig;
 
 #[cfg(feature = "bstd")]
 mod bstd;
+#[cfg(feature = "bstd")]
+pub use bstd::snippet;
+pub mod config;
 pub mod plugin;
 pub mod template;
+#[cfg(test)]
+mod testing;
 
 #[derive(Debug)]
-pub struct Custom {
+pub struct Custom<'a> {
     name: String,
     id: u32,
     data: HashMap<String, String>,
@@ -21,10 +30,21 @@ pub struct Custom {
     body: Option<String>,
     template: Option<template::Template>,
     plugin: Option<Box<dyn plugin::Plugin>>,
+    config: &'a CustomConfig,
+    warnings: &'a Warnings,
+    /// The line the element is on in the markdown file, 0 if it is not known
+    line: usize,
 }
 
-impl Custom {
-    pub fn new(name: String, data: HashMap<String, String>, body: Option<String>, id: u32) -> Self {
+impl<'a> Custom<'a> {
+    pub fn new(
+        name: String,
+        data: HashMap<String, String>,
+        body: Option<String>,
+        id: u32,
+        config: &'a CustomConfig,
+        warnings: &'a Warnings,
+    ) -> Self {
         Custom {
             name,
             id,
@@ -32,11 +52,28 @@ impl Custom {
             body,
             template: None,
             plugin: None,
+            config,
+            warnings,
+            line: 0,
         }
     }
 
-    pub fn from_elm(elm: &CustomElm, id: u32) -> Self {
-        Self::new(elm.name.clone(), elm.values.clone(), elm.body.clone(), id)
+    pub fn from_elm(
+        elm: &CustomElm,
+        id: u32,
+        config: &'a CustomConfig,
+        warnings: &'a Warnings,
+    ) -> Self {
+        let mut custom = Self::new(
+            elm.name.clone(),
+            elm.values.clone(),
+            elm.body.clone(),
+            id,
+            config,
+            warnings,
+        );
+        custom.line = elm.line;
+        custom
     }
 
     #[cfg(not(feature = "bstd"))]
@@ -70,7 +107,7 @@ impl Custom {
 
     fn run(&mut self, target: &mut Parser, others: &[Option<&Parser>]) {
         if let Some(mut p) = self.plugin.take() {
-            p.run(target, others);
+            p.run(target, others, self.warnings);
             self.plugin = Some(p);
         }
     }
@@ -104,13 +141,19 @@ fn get_customs(elements: Vec<Element>, mut current_list: Vec<u32>) -> Vec<u32> {
     current_list
 }
 
-fn run_customs(target: &mut Parser, others: &[Option<&Parser>], custom_elms: &[u32]) {
+fn run_customs(
+    target: &mut Parser,
+    others: &[Option<&Parser>],
+    custom_elms: &[u32],
+    config: &CustomConfig,
+    warnings: &Warnings,
+) {
     let mut customs: Vec<Custom> = custom_elms
         .iter()
         .filter_map(|id| {
             let e = target.ast.find(*id).expect("Should be there").clone();
             if let Kind::CustomElement(c) = &e.kind {
-                let mut custom = Custom::from_elm(c, e.get_id());
+                let mut custom = Custom::from_elm(c, e.get_id(), config, warnings);
                 custom.find();
                 custom.pre_load(target);
                 Some(custom)
@@ -126,10 +169,40 @@ fn run_customs(target: &mut Parser, others: &[Option<&Parser>], custom_elms: &[u
     }
 }
 
-pub fn parse_custom(mut target: Parser, others: &[Option<&Parser>]) -> Parser {
+/// Generates the extra pages (ie. tag pages) from all the files
+/// These should be lexed and parsed, then passed to `parse_custom` along with the other files
+#[cfg(feature = "bstd")]
+pub fn gen_pages(files: &[&Parser], urls: &Urls, warnings: &Warnings) -> Vec<MarkdownFile> {
+    bstd::gen_pages(files, urls, warnings)
+}
+
+#[cfg(not(feature = "bstd"))]
+pub fn gen_pages(_files: &[&Parser], _urls: &Urls, _warnings: &Warnings) -> Vec<MarkdownFile> {
+    vec![]
+}
+
+/// Replaces the `include` elements in a page with the content of the files
+/// Run this on every page before `parse_custom`, so the other pages see the content and when it was modified
+#[cfg(feature = "bstd")]
+pub fn expand_includes(target: &mut Parser, config: &CustomConfig, warnings: &Warnings) {
+    bstd::include::expand(target, config, warnings);
+}
+
+#[cfg(not(feature = "bstd"))]
+pub fn expand_includes(_target: &mut Parser, _config: &CustomConfig, _warnings: &Warnings) {}
+
+pub fn parse_custom(
+    mut target: Parser,
+    others: &[Option<&Parser>],
+    config: &CustomConfig,
+    warnings: &Warnings,
+) -> Parser {
     // if !target.has_custom {
     //     return target;
     // }
+    #[cfg(feature = "bstd")]
+    bstd::wiki::resolve(&mut target, others, &config.urls, warnings);
+
     let mut old_elms: Vec<u32> = vec![];
 
     loop {
@@ -150,13 +223,26 @@ pub fn parse_custom(mut target: Parser, others: &[Option<&Parser>]) -> Parser {
             None => new_elms.push(add_navbar(&mut target)),
         }
 
+        #[cfg(feature = "bstd")]
+        if config.footer.enabled && bstd::footer::should_add(&target) {
+            let has_footer = new_elms.iter().any(|e| {
+                matches!(
+                    &target.ast.find(*e).unwrap().kind,
+                    Kind::CustomElement(c) if c.name.to_lowercase() == bstd::footer::NAME
+                )
+            });
+            if !has_footer {
+                new_elms.push(bstd::footer::add_footer(&mut target));
+            }
+        }
+
         if new_elms == old_elms {
             break;
         }
 
         let mut run_elms = new_elms.clone();
         run_elms.retain(|e| !old_elms.contains(e));
-        run_customs(&mut target, others, &run_elms);
+        run_customs(&mut target, others, &run_elms, config, warnings);
 
         old_elms = new_elms;
     }
@@ -209,9 +295,15 @@ mod test {
             Err(e) => panic!("{e}"),
         }
 
-        let customs = format!("{:#?}", parse_custom(parser, &[]));
+        let customs = format!(
+            "{:#?}",
+            parse_custom(parser, &[], &CustomConfig::default(), &Warnings::default())
+        );
         let re = Regex::new(r"id: \d+").unwrap();
-        re.replace_all(&customs, "id: [redacted]").to_string()
+        let customs = re.replace_all(&customs, "id: [redacted]");
+        // the default copyright has the current year
+        let re = Regex::new(r"© \d{4}").unwrap();
+        re.replace_all(&customs, "© [year]").to_string()
     }
 
     macro_rules! snapshot {
@@ -229,6 +321,11 @@ mod test {
 
     snapshot!(test_plugin_2, "%{{\nname: bloglist\nother: key\n}}");
 
+    snapshot!(
+        test_plugin_tags,
+        "---\ntags:\n    - rust\n    - Web Dev\n---\n%{{\nname: tags\n}}"
+    );
+
     snapshot!(test_template, "%{{\nname: footer\n}}");
     snapshot!(
         test_template_2,
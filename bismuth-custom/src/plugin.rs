@@ -1,7 +1,8 @@
 use crate::Custom;
+use bismuth_error::warning::Warnings;
 use bismuth_parser::Parser;
 
 pub trait Plugin: std::fmt::Debug {
-    fn run(&mut self, target: &mut Parser, others: &[Option<&Parser>]);
+    fn run(&mut self, target: &mut Parser, others: &[Option<&Parser>], warnings: &Warnings);
     fn pre_load(&mut self, target: &Parser, custom: &Custom);
 }
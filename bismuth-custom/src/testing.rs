@@ -0,0 +1,66 @@
+//! Helpers for the tests of the plugins
+use bismuth_error::warning::Warnings;
+use bismuth_parser::{
+    tree::{Element, Kind},
+    Parser,
+};
+use std::collections::HashMap;
+
+use crate::{config::CustomConfig, parse_custom};
+
+/// Snapshots are kept in `testdata/output/utils/`, wherever the test is
+macro_rules! snapshot {
+    ($content:expr) => {
+        let mut settings = insta::Settings::clone_current();
+        settings.set_snapshot_path(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/testdata/output/utils/"
+        ));
+        settings.bind(|| {
+            insta::assert_snapshot!($content);
+        });
+    };
+}
+pub(crate) use snapshot;
+
+/// Parses `content` as the page at `path`
+pub fn page(path: &str, content: &str) -> Parser {
+    let mut parser = Parser::new_test(path, content);
+    parser.parse().unwrap();
+    parser
+}
+
+/// Runs the custom elements on `page`, `others` are the other pages of the site
+pub fn run(page: Parser, others: &[&Parser], config: &CustomConfig, warnings: &Warnings) -> Parser {
+    let others = others.iter().copied().map(Some).collect::<Vec<_>>();
+    parse_custom(page, &others, config, warnings)
+}
+
+/// Finds the custom elements called `name` (ie. the wrapper of a plugin)
+pub fn customs<'a>(elements: &'a [Element], name: &str) -> Vec<&'a Element> {
+    let mut found = vec![];
+    for element in elements {
+        if matches!(&element.kind, Kind::CustomElement(c) if c.name == name) {
+            found.push(element);
+        }
+        found.append(&mut customs(&element.elements, name));
+    }
+    found
+}
+
+/// The values of a custom element
+pub fn values(element: &Element) -> &HashMap<String, String> {
+    match &element.kind {
+        Kind::CustomElement(c) => &c.values,
+        _ => panic!("{:?} is not a custom element", element.kind),
+    }
+}
+
+/// The errors that stop the build, as they are shown
+pub fn errors(warnings: &Warnings) -> Vec<String> {
+    warnings
+        .take_errors()
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
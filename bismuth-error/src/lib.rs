@@ -1,5 +1,6 @@
 pub mod path;
 mod tui;
+pub mod warning;
 
 pub struct State {
     pub position: usize,
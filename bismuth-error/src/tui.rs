@@ -21,7 +21,7 @@ pub fn init_options(options: &[String], description: &str) -> Result<()> {
         stdout(),
         Print(message),
         SetForegroundColor(Color::Green),
-        Print(format!("> {}\n", options.get(0).unwrap())),
+        Print(format!("> {}\n", options.first().unwrap())),
         ResetColor,
         // Print("Option 2"),
         // cursor::RestorePosition
@@ -0,0 +1,167 @@
+use std::fmt::Display;
+use std::io::stdout;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+
+/// Something that is wrong with a file, but does not stop the build
+/// Ie. a blog post without a date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// The file that has the problem
+    pub path: PathBuf,
+    /// The element that found the problem, if any
+    pub element: Option<String>,
+    /// The line in the file, if it is known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(path: PathBuf, element: Option<&str>, message: String) -> Self {
+        Warning {
+            path,
+            element: element.map(String::from),
+            line: None,
+            message,
+        }
+    }
+
+    /// Lines that are 0 are not known, so they are ignored
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line).filter(|l| l > &0);
+        self
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut path = self.path.to_string_lossy().replace('\\', "/");
+        if let Some(line) = self.line {
+            path.push_str(&format!(":{line}"));
+        }
+        match &self.element {
+            Some(element) => write!(f, "{path} ({element}): {}", self.message),
+            None => write!(f, "{path}: {}", self.message),
+        }
+    }
+}
+
+/// The warnings and errors found while building, these are owned by the build and passed to what reports them
+/// They are printed together, so every problem is shown before the build stops
+#[derive(Debug, Default)]
+pub struct Warnings {
+    warnings: Mutex<Vec<Warning>>,
+    errors: Mutex<Vec<Warning>>,
+}
+
+impl Warnings {
+    /// Reports a warning, the same warning is only kept once
+    pub fn warn(&self, warning: Warning) {
+        let mut warnings = self.warnings.lock().unwrap();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    /// Reports something that should stop the build, ie. a file that is included but does not exist
+    pub fn error(&self, error: Warning) {
+        let mut errors = self.errors.lock().unwrap();
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+    }
+
+    /// Takes all the warnings that have been reported so far
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    /// Takes all the errors that have been reported so far
+    pub fn take_errors(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.errors.lock().unwrap())
+    }
+
+    /// Prints and clears all the warnings and errors that have been reported so far
+    /// Returns how many errors there were
+    pub fn print(&self) -> usize {
+        for warning in self.take_warnings() {
+            let _ = execute!(
+                stdout(),
+                SetForegroundColor(Color::Yellow),
+                Print("Warning: "),
+                ResetColor,
+                Print(format!("{warning}\n")),
+            );
+        }
+        let errors = self.take_errors();
+        for error in &errors {
+            let _ = execute!(
+                stdout(),
+                SetForegroundColor(Color::Red),
+                Print("Error: "),
+                ResetColor,
+                Print(format!("{error}\n")),
+            );
+        }
+        errors.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn warnings() {
+        let warnings = Warnings::default();
+        let warning = Warning::new(
+            PathBuf::from("/blogs/post.md"),
+            Some("blog list"),
+            String::from("missing `date`"),
+        );
+        warnings.warn(warning.clone());
+        warnings.warn(warning.clone());
+        warnings.warn(Warning::new(
+            PathBuf::from("/index.md"),
+            None,
+            String::from("missing `title`"),
+        ));
+
+        let found = warnings
+            .take_warnings()
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            found,
+            [
+                "/blogs/post.md (blog list): missing `date`",
+                "/index.md: missing `title`"
+            ]
+        );
+        assert!(warnings.take_warnings().is_empty());
+
+        warnings.error(
+            Warning::new(
+                PathBuf::from("/index.md"),
+                Some("include_code"),
+                String::from("`main.rs` does not exist"),
+            )
+            .with_line(4),
+        );
+        let errors = warnings
+            .take_errors()
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            errors,
+            ["/index.md:4 (include_code): `main.rs` does not exist"]
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use bismuth_parser::url::{is_external, resolve, Urls};
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::write::{utils::files, Dirs, BUILD_ASSETS};
+
+/// Written to the root of the output dir
+pub const MANIFEST: &str = "asset-manifest.json";
+
+/// The images made by the image pipeline already have a hash in their name
+const SKIP: &str = "assets/images";
+
+/// Maps the original path of a asset to the hashed path
+/// Ie `/assets/css/base.css` -> `/assets/css/base.3f2a1c9e.css`
+pub type Manifest = BTreeMap<String, String>;
+
+fn url(out: &Path, path: &Path) -> String {
+    let rel = path.strip_prefix(out).expect("Should be inside of out");
+    format!("/{}", rel.to_string_lossy().replace('\\', "/"))
+}
+
+/// Ie `base.css` -> `base.3f2a1c9e.css`
+fn hashed_name(path: &Path, content: &[u8]) -> PathBuf {
+    let hash = blake3::hash(content).to_hex();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}.{}.{}", &hash[..8], ext.to_string_lossy()),
+        None => format!("{stem}.{}", &hash[..8]),
+    };
+    path.with_file_name(name)
+}
+
+/// The asset a url in the file at `from` is to, from the root of the site
+/// Ie `../assets/a.css` in `/blogs/post.html` -> `/assets/a.css`
+fn target(url: &str, from: &str, urls: &Urls) -> Option<String> {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let path = urls
+        .strip(&url[..end])
+        .unwrap_or_else(|| url[..end].to_string());
+    if path.is_empty() || is_external(&path) {
+        return None;
+    }
+    Some(resolve(from, &path))
+}
+
+/// Swaps the name of the file in a url for the hashed name, so the url keeps its form
+/// Ie `../assets/a.css?v=1` and `/assets/a.1a2b3c4d.css` -> `../assets/a.1a2b3c4d.css?v=1`
+fn swap_name(url: &str, hashed: &str) -> String {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, rest) = url.split_at(end);
+    let dir = &path[..path.rfind('/').map(|i| i + 1).unwrap_or_default()];
+    let name = &hashed[hashed.rfind('/').map(|i| i + 1).unwrap_or_default()..];
+    format!("{dir}{name}{rest}")
+}
+
+/// Calls `f` on every url in the file, and replaces the url with what it returns
+/// In html these are the `href`, `src` and `srcset` attributes, and `url()` in styles
+/// In css these are `url()` and `@import`
+fn map_urls(content: &str, css: bool, mut f: impl FnMut(&str) -> String) -> String {
+    let style = Regex::new(r#"(url\(\s*["']?)([^"')\s]+)"#).expect("Should be valid regex");
+    let content = style.replace_all(content, |caps: &Captures| {
+        format!("{}{}", &caps[1], f(&caps[2]))
+    });
+    if css {
+        let import = Regex::new(r#"(@import\s+["'])([^"']+)"#).expect("Should be valid regex");
+        return import
+            .replace_all(&content, |caps: &Captures| {
+                format!("{}{}", &caps[1], f(&caps[2]))
+            })
+            .to_string();
+    }
+
+    let attr = Regex::new(r#"(?i)(\s(?:href|src)\s*=\s*["']?)([^"'\s>]+)"#)
+        .expect("Should be valid regex");
+    let content = attr.replace_all(&content, |caps: &Captures| {
+        format!("{}{}", &caps[1], f(&caps[2]))
+    });
+    let srcset = Regex::new(r#"(?i)(\ssrcset\s*=\s*")([^"]*)"#).expect("Should be valid regex");
+    let candidate = Regex::new(r"(^|,)(\s*)([^\s,]+)").expect("Should be valid regex");
+    srcset
+        .replace_all(&content, |caps: &Captures| {
+            let set = candidate.replace_all(&caps[2], |c: &Captures| {
+                format!("{}{}{}", &c[1], &c[2], f(&c[3]))
+            });
+            format!("{}{set}", &caps[1])
+        })
+        .to_string()
+}
+
+/// The assets in the manifest that a css file uses
+fn dependencies(content: &str, from: &str, urls: &Urls) -> Vec<String> {
+    let mut found = vec![];
+    map_urls(content, true, |url| {
+        found.extend(target(url, from, urls));
+        url.to_string()
+    });
+    found
+}
+
+/// Replaces every url to a asset in the manifest, in the file at `from`
+/// Urls can be from the root, have the base url, or be relative to the file, and can have a query or fragment after them
+pub fn rewrite(content: &str, from: &str, css: bool, manifest: &Manifest, urls: &Urls) -> String {
+    map_urls(content, css, |url| {
+        match target(url, from, urls).and_then(|t| manifest.get(&t)) {
+            Some(hashed) => swap_name(url, hashed),
+            None => url.to_string(),
+        }
+    })
+}
+
+fn is_css(path: &Path) -> bool {
+    path.extension().map(|e| e == "css").unwrap_or_default()
+}
+
+/// Hashes a asset, after the assets it uses, so the urls to them are updated first
+/// Assets that use each other are hashed in the order they are found
+fn hash_asset(
+    asset: &str,
+    assets: &BTreeMap<String, PathBuf>,
+    visiting: &mut Vec<String>,
+    manifest: &mut Manifest,
+    urls: &Urls,
+) -> Result<(), Error> {
+    if manifest.contains_key(asset) || visiting.iter().any(|a| a == asset) {
+        return Ok(());
+    }
+    let path = &assets[asset];
+    visiting.push(asset.to_string());
+    if is_css(path) {
+        let content = fs::read_to_string(path)?;
+        for dependency in dependencies(&content, asset, urls) {
+            if assets.contains_key(&dependency) {
+                hash_asset(&dependency, assets, visiting, manifest, urls)?;
+            }
+        }
+        fs::write(path, rewrite(&content, asset, true, manifest, urls))?;
+    }
+    visiting.pop();
+
+    let content = fs::read(path)?;
+    let new_path = hashed_name(path, &content);
+    fs::rename(path, &new_path)?;
+    let name = new_path.file_name().unwrap_or_default().to_string_lossy();
+    manifest.insert(asset.to_string(), swap_name(asset, &name));
+    Ok(())
+}
+
+/// Adds a hash of the content to the name of every asset in the output dir
+/// Then updates the urls to them in the html and css, and writes the manifest
+pub fn fingerprint(dirs: &Dirs, urls: &Urls) -> Result<Manifest, Error> {
+    let mut asset_dirs = vec![dirs.out.join(BUILD_ASSETS)];
+    if let Ok(rel) = dirs.assets.strip_prefix(&dirs.project) {
+        let dir = dirs.out.join(rel);
+        if !asset_dirs.contains(&dir) {
+            asset_dirs.push(dir);
+        }
+    }
+
+    let skip = dirs.out.join(SKIP);
+    let mut assets = BTreeMap::new();
+    for dir in &asset_dirs {
+        for path in files(dir, &[])? {
+            if !path.starts_with(&skip) {
+                assets.insert(url(&dirs.out, &path), path);
+            }
+        }
+    }
+
+    let mut manifest = Manifest::new();
+    for asset in assets.keys() {
+        hash_asset(asset, &assets, &mut vec![], &mut manifest, urls)?;
+    }
+
+    for path in files(&dirs.out, &[])? {
+        if !path.extension().map(|e| e == "html").unwrap_or_default() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let from = url(&dirs.out, &path);
+        fs::write(&path, rewrite(&content, &from, false, &manifest, urls))?;
+    }
+
+    let json = serde_json::to_string_pretty(&manifest)
+        .expect("Should be able to serialize a map of strings");
+    fs::write(dirs.out.join(MANIFEST), json)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bismuth_test::project;
+
+    #[test]
+    fn rewrite_urls() {
+        let manifest = Manifest::from([
+            (
+                String::from("/assets/a.css"),
+                String::from("/assets/a.1.css"),
+            ),
+            (
+                String::from("/assets/b.png"),
+                String::from("/assets/b.2.png"),
+            ),
+        ]);
+        let urls = Urls::new("https://example.com/docs", Default::default());
+        let html = r#"<link href="../assets/a.css"><img src='/assets/b.png?v=1' srcset="../assets/b.png 480w, https://example.com/docs/assets/b.png 960w"><p>see assets/b.png</p><div style="background: url(../assets/b.png)">"#;
+        assert_eq!(
+            rewrite(html, "/blogs/post.html", false, &manifest, &urls),
+            r#"<link href="../assets/a.1.css"><img src='/assets/b.2.png?v=1' srcset="../assets/b.2.png 480w, https://example.com/docs/assets/b.2.png 960w"><p>see assets/b.png</p><div style="background: url(../assets/b.2.png)">"#
+        );
+
+        let css = r#"@import "a.css"; body { background: url('b.png'); content: "b.png"; }"#;
+        assert_eq!(
+            rewrite(css, "/assets/c.css", true, &manifest, &urls),
+            r#"@import "a.1.css"; body { background: url('b.2.png'); content: "b.png"; }"#
+        );
+    }
+
+    #[test]
+    fn fingerprint_test() {
+        let project = project(&[
+            (
+                "build/assets/css/base.css",
+                r#"@import "theme.css"; body { background: url(/assets/bg.png); }"#,
+            ),
+            (
+                "build/assets/css/theme.css",
+                "body { background: url(../bg.png); }",
+            ),
+            ("build/assets/bg.png", "png"),
+            ("build/assets/images/bg-1a2b3c4d-480.png", "png"),
+            ("build/CNAME", "example.com"),
+            (
+                "build/index.html",
+                r#"<link rel="stylesheet" href="/assets/css/base.css"><img src="assets/bg.png?v=1"><img src="../assets/bg.png"><a href="/other.html">"#,
+            ),
+        ]);
+
+        let dirs = Dirs::new(project.path(), "src", "build", "assets", "static");
+        let manifest = fingerprint(&dirs, &Default::default()).unwrap();
+
+        let bg = manifest.get("/assets/bg.png").unwrap();
+        let css = manifest.get("/assets/css/base.css").unwrap();
+        let theme = manifest.get("/assets/css/theme.css").unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert!(Regex::new(r"^/assets/css/base\.[0-9a-f]{8}\.css$")
+            .unwrap()
+            .is_match(css));
+
+        let out = &dirs.out;
+        assert!(!out.join("assets/bg.png").exists());
+        assert!(out.join("assets/images/bg-1a2b3c4d-480.png").exists());
+        assert!(out.join("CNAME").exists());
+        let css_content = fs::read_to_string(out.join(&css[1..])).unwrap();
+        assert!(css_content.contains(&format!("url({bg})")));
+        assert!(css_content.contains(&format!(r#"@import "{}""#, &theme[12..])));
+        let theme_content = fs::read_to_string(out.join(&theme[1..])).unwrap();
+        assert!(theme_content.contains(&format!("url(..{})", &bg[7..])));
+
+        let html = fs::read_to_string(out.join("index.html")).unwrap();
+        assert_eq!(
+            html,
+            format!(
+                r#"<link rel="stylesheet" href="{css}"><img src="{}?v=1"><img src="../{0}"><a href="/other.html">"#,
+                &bg[1..]
+            )
+        );
+        assert!(out.join(MANIFEST).exists());
+    }
+}
@@ -0,0 +1,353 @@
+use bismuth_error::warning::{Warning, Warnings};
+use bismuth_parser::url::Urls;
+use image::{
+    codecs::avif::AvifEncoder, imageops::FilterType, DynamicImage, ImageFormat, ImageResult,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::write::Dirs;
+
+/// Where the processed images are put, inside of the output dir
+const IMAGES_DIR: &str = "assets/images";
+
+/// The `[images]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ImageConfig {
+    pub enabled: bool,
+    /// The widths to make variants at, widths bigger than the image are skipped
+    /// None are made by default
+    pub sizes: Vec<u32>,
+    /// The extra formats to make, `webp` and/or `avif`
+    /// None are made by default, avif is slow to make
+    pub formats: Vec<String>,
+    /// 1-100, used for avif and jpeg
+    pub quality: u8,
+    /// Where processed images are cached, relative to the project dir
+    pub cache: String,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sizes: vec![],
+            formats: vec![],
+            quality: 80,
+            cache: String::from(".bismuth-cache/images"),
+        }
+    }
+}
+
+/// A resized copy of a image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub url: String,
+    pub width: u32,
+    pub mime: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedImage {
+    pub width: u32,
+    pub height: u32,
+    /// The variants in the same format as the image
+    pub fallback: Vec<Variant>,
+    /// The variants in the modern formats, grouped by format
+    pub sources: Vec<Vec<Variant>>,
+}
+
+impl ProcessedImage {
+    fn srcset(variants: &[Variant], from: &str, urls: &Urls) -> String {
+        variants
+            .iter()
+            .map(|v| format!("{} {}w", urls.url(&v.url, from), v.width))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Makes the `<picture>` element for the image, on the page at `from`
+    pub fn to_html(&self, src: &str, alt: &str, from: &str, urls: &Urls) -> String {
+        let sizes = format!("(max-width: {0}px) 100vw, {0}px", self.width);
+        let sources = self
+            .sources
+            .iter()
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                format!(
+                    r#"<source type="{}" srcset="{}" sizes="{sizes}">"#,
+                    s[0].mime,
+                    Self::srcset(s, from, urls)
+                )
+            })
+            .collect::<String>();
+        let srcset = match self.fallback.is_empty() {
+            true => String::new(),
+            false => format!(
+                r#" srcset="{}" sizes="{sizes}""#,
+                Self::srcset(&self.fallback, from, urls)
+            ),
+        };
+        format!(
+            r#"<picture>{sources}<img src="{src}"{srcset} alt="{alt}" width="{}" height="{}" loading="lazy" decoding="async"></picture>"#,
+            self.width, self.height
+        )
+    }
+}
+
+/// Makes the images for the site, and caches them by the hash of the image
+#[derive(Debug)]
+pub struct ImagePipeline {
+    config: ImageConfig,
+    dirs: Dirs,
+    cache_dir: PathBuf,
+    /// So images used more than once are only processed once
+    processed: Mutex<HashMap<PathBuf, Option<ProcessedImage>>>,
+    /// The images that could not be processed, the build reports these
+    pub warnings: Warnings,
+}
+
+fn mime(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Avif => "image/avif",
+        ImageFormat::WebP => "image/webp",
+        ImageFormat::Jpeg => "image/jpeg",
+        _ => "image/png",
+    }
+}
+
+impl ImagePipeline {
+    pub fn new(config: ImageConfig, dirs: &Dirs) -> Self {
+        ImagePipeline {
+            cache_dir: dirs.project.join(&config.cache),
+            config,
+            dirs: dirs.clone(),
+            processed: Mutex::new(HashMap::new()),
+            warnings: Warnings::default(),
+        }
+    }
+
+    /// Gets the size of the image, and makes its variants
+    /// Returns `None` if the image could not be processed, this is reported as a warning
+    pub fn process(&self, src: &str) -> Option<ProcessedImage> {
+        if !self.config.enabled {
+            return None;
+        }
+        let path = self.dirs.source(Path::new(src));
+        if let Some(processed) = self.processed.lock().unwrap().get(&path) {
+            return processed.clone();
+        }
+
+        let processed = match self.process_file(&path) {
+            Ok(processed) => Some(processed),
+            // images that do not exist are reported when they are copied
+            Err(_) if !path.exists() => None,
+            Err(e) => {
+                self.warnings
+                    .warn(Warning::new(PathBuf::from(src), Some("images"), e));
+                None
+            }
+        };
+        self.processed
+            .lock()
+            .unwrap()
+            .insert(path, processed.clone());
+        processed
+    }
+
+    fn process_file(&self, path: &Path) -> Result<ProcessedImage, String> {
+        let bytes = fs::read(path).map_err(|e| format!("could not be read: {e}"))?;
+        let (width, height) = image::ImageReader::new(Cursor::new(&bytes))
+            .with_guessed_format()
+            .map_err(|e| format!("could not be read: {e}"))?
+            .into_dimensions()
+            .map_err(|e| format!("could not be decoded: {e}"))?;
+        let format =
+            image::guess_format(&bytes).map_err(|e| format!("could not be decoded: {e}"))?;
+
+        let mut widths = self
+            .config
+            .sizes
+            .iter()
+            .copied()
+            .filter(|w| w < &width)
+            .collect::<Vec<u32>>();
+        widths.push(width);
+        widths.sort();
+        widths.dedup();
+
+        let mut formats = vec![format];
+        for f in &self.config.formats {
+            match f.to_lowercase().as_str() {
+                "avif" => formats.insert(0, ImageFormat::Avif),
+                "webp" if format != ImageFormat::WebP => formats.insert(0, ImageFormat::WebP),
+                _ => {}
+            }
+        }
+        // the best format should be first, so the browser picks it
+        formats.sort_by_key(|f| match f {
+            ImageFormat::Avif => 0,
+            ImageFormat::WebP => 1,
+            _ => 2,
+        });
+        formats.dedup();
+
+        // only these can be resized without losing anything (ie. the frames of a gif)
+        let resizable = matches!(
+            format,
+            ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP
+        );
+        // nothing to make, the image is used as is
+        if !resizable || (widths.len() == 1 && formats.len() == 1) {
+            return Ok(ProcessedImage {
+                width,
+                height,
+                fallback: vec![],
+                sources: vec![],
+            });
+        }
+
+        let hash = blake3::hash(&bytes).to_hex().to_string();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = format!("{stem}-{}", &hash[..8]);
+
+        let mut image: Option<DynamicImage> = None;
+        let mut groups = vec![];
+        for format in formats {
+            let mut variants = vec![];
+            for width in &widths {
+                let file_name = format!("{name}-{width}.{}", format.extensions_str()[0]);
+                let cached = self.cache_dir.join(&hash[..2]).join(&file_name);
+                if !cached.exists() {
+                    let image = match &image {
+                        Some(i) => i,
+                        None => image.insert(
+                            image::load_from_memory(&bytes)
+                                .map_err(|e| format!("could not be decoded: {e}"))?,
+                        ),
+                    };
+                    let resized = image.resize(*width, u32::MAX, FilterType::Lanczos3);
+                    let encoded = self
+                        .encode(&resized, format)
+                        .map_err(|e| format!("could not be made into {}: {e}", mime(format)))?;
+                    write_file(&cached, &encoded)?;
+                }
+
+                let out = self.dirs.out.join(IMAGES_DIR).join(&file_name);
+                let copied = fs::create_dir_all(self.dirs.out.join(IMAGES_DIR))
+                    .and_then(|_| fs::copy(&cached, &out));
+                copied.map_err(|e| format!("`{}` could not be written: {e}", out.display()))?;
+                variants.push(Variant {
+                    url: format!("/{IMAGES_DIR}/{file_name}"),
+                    width: *width,
+                    mime: mime(format),
+                });
+            }
+            groups.push(variants);
+        }
+
+        let fallback = groups.pop().unwrap_or_default();
+        Ok(ProcessedImage {
+            width,
+            height,
+            fallback,
+            sources: groups,
+        })
+    }
+
+    fn encode(&self, image: &DynamicImage, format: ImageFormat) -> ImageResult<Vec<u8>> {
+        let mut out = Cursor::new(vec![]);
+        let quality = self.config.quality.clamp(1, 100);
+        match format {
+            ImageFormat::Avif => {
+                let encoder = AvifEncoder::new_with_speed_quality(&mut out, 8, quality);
+                image.write_with_encoder(encoder)?;
+            }
+            ImageFormat::Jpeg => {
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
+                image.to_rgb8().write_with_encoder(encoder)?;
+            }
+            // webp is always lossless
+            _ => image.write_to(&mut out, format)?,
+        }
+        Ok(out.into_inner())
+    }
+}
+
+/// Writes a file to the cache, making its dir if needed
+fn write_file(path: &Path, content: &[u8]) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(path, content))
+        .map_err(|e| format!("`{}` could not be cached: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bismuth_test::project;
+
+    #[test]
+    fn process_image() {
+        let dir = project(&[("assets/broken.png", "not a png")]);
+        let project = dir.path();
+        image::RgbImage::from_pixel(200, 100, image::Rgb([200, 100, 50]))
+            .save(project.join("assets/test.png"))
+            .unwrap();
+
+        let dirs = Dirs::new(project, "src", "build", "assets", "static");
+        let config = ImageConfig {
+            sizes: vec![100, 400],
+            formats: vec![String::from("webp")],
+            ..Default::default()
+        };
+        let pipeline = ImagePipeline::new(config, &dirs);
+        let image = pipeline.process("/assets/test.png").unwrap();
+
+        assert_eq!((image.width, image.height), (200, 100));
+        let urls = |variants: &[Variant]| {
+            variants
+                .iter()
+                .map(|v| v.url.rsplit('-').next().unwrap().to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(urls(&image.fallback), ["100.png", "200.png"]);
+        assert_eq!(urls(&image.sources[0]), ["100.webp", "200.webp"]);
+        for variant in image.fallback.iter().chain(&image.sources[0]) {
+            assert!(dirs.out.join(variant.url.trim_start_matches('/')).exists());
+        }
+
+        let html = image.to_html(
+            "/assets/test.png",
+            "test",
+            "/index.html",
+            &Default::default(),
+        );
+        assert!(html.contains(r#"width="200" height="100" loading="lazy""#));
+        assert!(html.contains(r#"<source type="image/webp""#));
+
+        // the variants are cached
+        assert!(dirs.project.join(".bismuth-cache/images").exists());
+        assert!(pipeline.process("/assets/missing.png").is_none());
+        assert!(pipeline.warnings.take_warnings().is_empty());
+
+        assert!(pipeline.process("/assets/broken.png").is_none());
+        let warnings = pipeline.warnings.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, PathBuf::from("/assets/broken.png"));
+
+        // by default the image is only measured
+        let pipeline = ImagePipeline::new(Default::default(), &dirs);
+        let image = pipeline.process("/assets/test.png").unwrap();
+        assert_eq!((image.width, image.height), (200, 100));
+        assert!(image.fallback.is_empty() && image.sources.is_empty());
+    }
+}
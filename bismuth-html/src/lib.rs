@@ -1,25 +1,33 @@
 use bismuth_parser::Parser;
 use std::path::PathBuf;
 
+pub mod fingerprint;
+pub mod image;
+pub mod links;
+pub mod minify;
+pub mod redirect;
 mod render;
+pub mod search;
+pub mod seo;
 mod template;
 pub mod write;
 
-pub use crate::render::{Render, Renderer};
+pub use crate::render::code as highlight;
+pub use crate::render::{Render, RenderConfig, Renderer};
 
 // Expose a api to just render a parser
 // + Make something to render a list of parsers concurrently
 
-pub fn render_one(parser: Parser) -> Option<String> {
-    let mut renderer = render::Renderer::new(parser);
-    renderer.render(&PathBuf::new())
+pub fn render_one(parser: Parser, config: &RenderConfig) -> Option<String> {
+    let mut renderer = render::Renderer::new(parser, &config.urls);
+    renderer.render(&PathBuf::new(), config)
 }
-pub fn render_list(parsers: Vec<Parser>) -> Vec<render::Renderer> {
+pub fn render_list(parsers: Vec<Parser>, config: &RenderConfig) -> Vec<render::Renderer> {
     parsers
         .iter()
         .map(|p| {
-            let mut renderer = render::Renderer::new(p.clone());
-            let _ = renderer.render(&PathBuf::new());
+            let mut renderer = render::Renderer::new(p.clone(), &config.urls);
+            let _ = renderer.render(&PathBuf::new(), config);
             renderer
         })
         .collect::<Vec<render::Renderer>>()
@@ -0,0 +1,279 @@
+use bismuth_parser::{
+    tree::{Element, Kind},
+    url::{self, is_external, resolve, Urls},
+    Parser,
+};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::write::utils::files;
+
+/// A link or image in a markdown file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// The markdown file, relative to the src dir
+    pub file: PathBuf,
+    pub line: usize,
+    /// The html file of the page it is on, ie `/blogs/post.html` or `/blogs/post/index.html` with pretty urls
+    pub page: String,
+    pub url: String,
+    /// Images and other files are from the project dir, not the page
+    pub asset: bool,
+}
+
+/// A reference to something that is not in the built site
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenLink {
+    pub reference: Reference,
+    pub reason: String,
+}
+
+/// The files in the built site, and the ids in each html file
+#[derive(Debug, Default)]
+pub struct Site {
+    files: BTreeSet<String>,
+    ids: BTreeMap<String, BTreeSet<String>>,
+}
+
+fn find_references(elements: &[Element], file: &Path, page: &str, references: &mut Vec<Reference>) {
+    for element in elements {
+        if let (Kind::Link | Kind::FilePrev, Ok(url)) = (&element.kind, element.get_attr("link")) {
+            let line = element
+                .get_attr("line")
+                .ok()
+                .and_then(|l| l.parse().ok())
+                .unwrap_or_default();
+            references.push(Reference {
+                file: file.to_path_buf(),
+                line,
+                page: page.to_string(),
+                url: url.trim().to_string(),
+                asset: element.kind == Kind::FilePrev,
+            });
+        }
+        find_references(&element.elements, file, page, references);
+    }
+}
+
+/// Finds all the links and images in the pages
+/// This should be done after running the customs, so `[[page]]` links have their urls
+pub fn references(parsers: &[Parser], urls: &Urls) -> Vec<Reference> {
+    let mut references = vec![];
+    for parser in parsers {
+        // the ids are found by the file, and relative links are from it, so `/blogs/post/` is `/blogs/post/index.html`
+        find_references(
+            &parser.ast.elements,
+            &parser.metadata.absolute_path,
+            &url::html_file(&urls.path(parser)),
+            &mut references,
+        );
+    }
+    references
+}
+
+impl Site {
+    /// Loads every file in the output dir, and the ids from the html files
+    pub fn load(out: &Path) -> Result<Self, Error> {
+        let id = Regex::new(r#"\sid\s*=\s*["']([^"']+)["']"#).expect("Should be valid regex");
+        let mut site = Site::default();
+        for path in files(out, &[])? {
+            let rel = path.strip_prefix(out).unwrap_or(&path);
+            let url = format!("/{}", rel.to_string_lossy().replace('\\', "/"));
+            if path.extension().map(|e| e == "html").unwrap_or_default() {
+                let content = fs::read_to_string(&path)?;
+                let ids = id
+                    .captures_iter(&content)
+                    .map(|c| c[1].to_string())
+                    .collect();
+                site.ids.insert(url.clone(), ids);
+            }
+            site.files.insert(url);
+        }
+        Ok(site)
+    }
+
+    /// Finds the file for a url, ie `/blogs/` -> `/blogs/index.html`
+    fn find(&self, url: &str) -> Option<String> {
+        let url = url.trim_end_matches('/');
+        [
+            url.to_string(),
+            format!("{url}.html"),
+            format!("{url}/index.html"),
+        ]
+        .into_iter()
+        .find(|u| self.files.contains(u))
+    }
+
+    /// Checks that the file (and the `#id`, if there is one) of a reference are in the site
+    pub fn check(&self, reference: &Reference, urls: &Urls) -> Result<(), String> {
+        if reference.url.is_empty() {
+            return Err(String::from("the link is empty"));
+        }
+        // links to other sites are not checked, but links with the base url are
+        let url = &urls
+            .strip(&reference.url)
+            .unwrap_or_else(|| reference.url.clone());
+        if is_external(url) {
+            return Ok(());
+        }
+        let (path, fragment) = match url.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (url.as_str(), None),
+        };
+        let path = path.split('?').next().unwrap_or_default();
+
+        let file = match path.is_empty() {
+            true => reference.page.clone(),
+            false => {
+                let resolved = match reference.asset {
+                    true => url::asset(path),
+                    false => resolve(&reference.page, path),
+                };
+                self.find(&resolved)
+                    .ok_or_else(|| format!("`{url}` does not exist ({resolved})"))?
+            }
+        };
+        match fragment {
+            Some(id) if !id.is_empty() => {
+                let has_id = self
+                    .ids
+                    .get(&file)
+                    .map(|ids| ids.contains(id))
+                    .unwrap_or_default();
+                match has_id {
+                    true => Ok(()),
+                    false => Err(format!("`{url}`: `{file}` does not have `#{id}`")),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Checks every reference against the built site in `out`
+pub fn check(references: &[Reference], urls: &Urls, out: &Path) -> Result<Vec<BrokenLink>, Error> {
+    let site = Site::load(out)?;
+    Ok(references
+        .iter()
+        .filter_map(|reference| {
+            site.check(reference, urls).err().map(|reason| BrokenLink {
+                reference: reference.clone(),
+                reason,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bismuth_parser::url::PermalinkConfig;
+    use bismuth_test::project;
+
+    #[test]
+    fn external_url() {
+        assert!(is_external("https://example.com"));
+        assert!(is_external("mailto:me@example.com"));
+        assert!(!is_external("/about.html"));
+        assert!(is_external("//cdn.example.com/app.js"));
+        assert!(!is_external("../1st:draft.html"));
+    }
+
+    #[test]
+    fn check_links() {
+        let out = project(&[
+            (
+                "index.html",
+                r#"<meta name="description" content="Hi"><h1 id="intro">Intro</h1>"#,
+            ),
+            ("blogs/post.html", r#"<h2 id="setup">Setup</h2>"#),
+            ("assets/a.png", "png"),
+        ]);
+
+        let reference = |page: &str, url: &str| Reference {
+            file: PathBuf::from("/index.md"),
+            line: 1,
+            page: page.to_string(),
+            url: url.to_string(),
+            asset: false,
+        };
+        let image = |page: &str, url: &str| Reference {
+            asset: true,
+            ..reference(page, url)
+        };
+        let references = [
+            reference("/index.html", "blogs/post.html#setup"),
+            reference("/index.html", "/assets/a.png"),
+            reference("/index.html", "#intro"),
+            reference("/index.html", "https://example.com/missing"),
+            reference("/blogs/post.html", "../index.html"),
+            reference("/blogs/post.html", "/"),
+            reference("/index.html", "/missing.html"),
+            reference("/index.html", "/blogs/post#intro"),
+            reference("/blogs/post.html", "#nope"),
+            image("/blogs/post.html", "assets/a.png"),
+            image("/blogs/post.html", "../assets/a.png"),
+            reference("/index.html", "#description"),
+        ];
+
+        let broken = check(&references, &Default::default(), out.path())
+            .unwrap()
+            .into_iter()
+            .map(|b| b.reason)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            broken,
+            [
+                "`/missing.html` does not exist (/missing.html)",
+                "`/blogs/post#intro`: `/blogs/post.html` does not have `#intro`",
+                "`#nope`: `/blogs/post.html` does not have `#nope`",
+                "`#description`: `/index.html` does not have `#description`",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_pretty_links() {
+        let out = project(&[
+            ("blogs/index.html", r#"<h1 id="blogs">Blogs</h1>"#),
+            ("blogs/post/index.html", r#"<h2 id="setup">Setup</h2>"#),
+            ("blogs/other/index.html", "Other"),
+        ]);
+        let urls = Urls::default().with_permalinks(PermalinkConfig {
+            pretty: true,
+            ..Default::default()
+        });
+        let pages = [
+            (
+                "/blogs/post.md",
+                "[a](#setup) [b](../other/) [c](#nope) [d](../missing/)",
+            ),
+            ("/blogs/index.md", "[a](#blogs) [b](post/#setup)"),
+        ]
+        .iter()
+        .map(|(path, content)| {
+            let mut parser = Parser::new_test(path, content);
+            parser.parse().unwrap();
+            parser
+        })
+        .collect::<Vec<Parser>>();
+
+        let references = references(&pages, &urls);
+        assert_eq!(references[0].page, "/blogs/post/index.html");
+        let broken = check(&references, &urls, out.path())
+            .unwrap()
+            .into_iter()
+            .map(|b| b.reason)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            broken,
+            [
+                "`#nope`: `/blogs/post/index.html` does not have `#nope`",
+                "`../missing/` does not exist (/blogs/missing/)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,572 @@
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::write::utils::files;
+
+/// The content of these is kept as is
+const KEEP: [&str; 3] = ["pre", "code", "textarea"];
+
+/// Whitespace around these tags does not change how the page looks
+const BLOCKS: [&str; 40] = [
+    "html",
+    "head",
+    "body",
+    "meta",
+    "link",
+    "title",
+    "style",
+    "script",
+    "base",
+    "div",
+    "p",
+    "ul",
+    "ol",
+    "li",
+    "nav",
+    "header",
+    "footer",
+    "main",
+    "section",
+    "article",
+    "aside",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "td",
+    "th",
+    "br",
+    "hr",
+    "pre",
+    "blockquote",
+    "form",
+    "figure",
+    "picture",
+];
+
+/// How much smaller a file was made
+#[derive(Debug, Clone, PartialEq)]
+pub struct Saving {
+    /// Relative to the output dir
+    pub path: PathBuf,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Saving {
+    pub fn saved(&self) -> usize {
+        self.before.saturating_sub(self.after)
+    }
+
+    pub fn percent(&self) -> f64 {
+        match self.before {
+            0 => 0.0,
+            before => self.saved() as f64 / before as f64 * 100.0,
+        }
+    }
+}
+
+/// Finds the end of a string starting at `start`, so comments inside of it are not removed
+fn skip_string(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != quote {
+        if chars[i] == '\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(chars.len())
+}
+
+/// Removes `/* */` comments, but not ones inside of strings
+fn remove_block_comments(content: &str) -> String {
+    let chars = content.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '"' || chars[i] == '\'' {
+            let end = skip_string(&chars, i);
+            output.extend(&chars[i..end]);
+            i = end;
+        } else if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else {
+            output.push(chars[i]);
+            i += 1;
+        }
+    }
+    output
+}
+
+/// Removes comments and whitespace from css
+pub fn css(content: &str) -> String {
+    let content = remove_block_comments(content);
+    let chars = content.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' || c == '\'' {
+            let end = skip_string(&chars, i);
+            output.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            let next = chars.get(i).copied().unwrap_or('{');
+            let prev = output.chars().last().unwrap_or('{');
+            // a space before `:` can be part of a selector, ie. `div :hover`
+            if !"{};,>".contains(next) && !"{};:,>".contains(prev) {
+                output.push(' ');
+            }
+            continue;
+        }
+        if c == '}' && output.ends_with(';') {
+            output.pop();
+        }
+        output.push(c);
+        i += 1;
+    }
+    output
+}
+
+/// A `/` after these starts a regex, not a division
+const REGEX_AFTER: &str = "(,=:[!&|?{};+-*%<>~^";
+const REGEX_KEYWORDS: [&str; 14] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "case",
+    "do",
+    "else",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "yield",
+    "await",
+];
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// If a `/` after the js so far starts a regex
+fn regex_allowed(output: &str) -> bool {
+    let Some(last) = output.trim_end().chars().last() else {
+        return true;
+    };
+    if !is_word(last) {
+        return REGEX_AFTER.contains(last);
+    }
+    let word = output
+        .trim_end()
+        .rsplit(|c: char| !is_word(c))
+        .next()
+        .unwrap_or_default();
+    REGEX_KEYWORDS.contains(&word)
+}
+
+/// The space between two tokens, if it is needed
+/// New lines are kept, so this does not break code that leaves out semicolons
+fn push_space(output: &mut String, next: char, newline: bool) {
+    let Some(prev) = output.chars().last() else {
+        return;
+    };
+    if newline {
+        output.push('\n');
+    } else if (is_word(prev) && (is_word(next) || next == '.'))
+        || (prev == next && "+-/".contains(next))
+    {
+        output.push(' ');
+    }
+}
+
+/// Copies a regex, `/` in a class (ie. `[/]`) does not end it
+fn copy_regex(chars: &[char], mut i: usize, output: &mut String) -> usize {
+    let mut class = false;
+    output.push('/');
+    i += 1;
+    while i < chars.len() && chars[i] != '\n' {
+        let c = chars[i];
+        output.push(c);
+        i += 1;
+        match c {
+            '\\' if i < chars.len() => {
+                output.push(chars[i]);
+                i += 1;
+            }
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => break,
+            _ => {}
+        }
+    }
+    i
+}
+
+/// Copies a template literal, the js in `${}` is minified
+fn copy_template(chars: &[char], mut i: usize, output: &mut String) -> usize {
+    output.push('`');
+    i += 1;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' {
+            output.extend(&chars[i..(i + 2).min(chars.len())]);
+            i += 2;
+        } else if c == '$' && chars.get(i + 1) == Some(&'{') {
+            output.push_str("${");
+            i = minify_js(chars, i + 2, output, true);
+            if i < chars.len() {
+                output.push('}');
+                i += 1;
+            }
+        } else {
+            output.push(c);
+            i += 1;
+            if c == '`' {
+                break;
+            }
+        }
+    }
+    i
+}
+
+/// Minifies js from `i`, strings, template literals and regexes are copied as is
+/// In a template literal, this stops at the `}` that closes the `${`
+fn minify_js(chars: &[char], mut i: usize, output: &mut String, template: bool) -> usize {
+    let mut depth = 0;
+    // the whitespace and comments since the last token, `Some(true)` if there was a new line
+    let mut space: Option<bool> = None;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            space = Some(space == Some(true) || c == '\n');
+            i += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            space = Some(space == Some(true));
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            let start = i;
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            space = Some(space == Some(true) || chars[start..i].contains(&'\n'));
+            continue;
+        }
+        if template && depth == 0 && c == '}' {
+            return i;
+        }
+
+        if let Some(newline) = space.take() {
+            push_space(output, c, newline);
+        }
+        match c {
+            '"' | '\'' => {
+                let end = skip_string(chars, i);
+                output.extend(&chars[i..end]);
+                i = end;
+            }
+            '`' => i = copy_template(chars, i, output),
+            '/' if regex_allowed(output) => i = copy_regex(chars, i, output),
+            _ => {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+/// Removes comments and whitespace from js
+/// Strings, template literals and regexes are kept as is
+pub fn js(content: &str) -> String {
+    let chars = content.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    minify_js(&chars, 0, &mut output, false);
+    output
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Collapses the whitespace in a tag, but not in its attributes
+fn minify_tag(tag: &str) -> String {
+    let chars = tag.chars().collect::<Vec<char>>();
+    let mut output = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if (c == '"' || c == '\'') && output.ends_with('=') {
+            let end = skip_string(&chars, i);
+            output.extend(&chars[i..end]);
+            i = end;
+        } else if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            if !matches!(chars.get(i), Some('>' | '/' | '=')) && !output.ends_with('=') {
+                output.push(' ');
+            }
+        } else {
+            output.push(c);
+            i += 1;
+        }
+    }
+    output
+}
+
+/// Finds the end of a tag, skipping `>` in attributes
+fn tag_end(html: &str, start: usize) -> usize {
+    let mut quote = None;
+    for (i, c) in html[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return start + i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Removes comments and whitespace from html
+/// The content of `<pre>`, `<code>` and `<textarea>` is kept as is, and inline css and js is minified
+pub fn html(content: &str) -> String {
+    let mut output = String::new();
+    let mut space = false;
+    let mut after_block = true;
+    let mut i = 0;
+
+    while i < content.len() {
+        let rest = &content[i..];
+        if rest.starts_with("<!--") && !rest.starts_with("<!--[if") {
+            i += rest.find("-->").map(|e| e + 3).unwrap_or(rest.len());
+            continue;
+        }
+
+        let c = rest.chars().next().expect("Should not be empty");
+        if c.is_whitespace() {
+            space = true;
+            i += c.len_utf8();
+            continue;
+        }
+
+        // a `<` that is not followed by a tag name is text, ie. `1 < 2`
+        let is_tag = c == '<'
+            && rest[1..].starts_with(|n: char| n.is_ascii_alphabetic() || "/!".contains(n));
+        if !is_tag {
+            if space && !after_block {
+                output.push(' ');
+            }
+            space = false;
+            after_block = false;
+            output.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        let end = tag_end(content, i);
+        let tag = &content[i..end];
+        let name = tag_name(tag);
+        let is_block = BLOCKS.contains(&name.as_str());
+        if space && !after_block && !is_block {
+            output.push(' ');
+        }
+        space = false;
+        after_block = is_block;
+        output.push_str(&minify_tag(tag));
+        i = end;
+
+        let raw = KEEP.contains(&name.as_str()) || name == "style" || name == "script";
+        if !raw || tag.starts_with("</") || tag.ends_with("/>") {
+            continue;
+        }
+        let close = format!("</{name}");
+        let inner_end = content[i..]
+            .to_ascii_lowercase()
+            .find(&close)
+            .map(|e| i + e)
+            .unwrap_or(content.len());
+        let inner = &content[i..inner_end];
+        let is_js = !tag.contains("type=") || tag.contains("javascript") || tag.contains("module");
+        match name.as_str() {
+            "style" => output.push_str(&css(inner)),
+            "script" if is_js => output.push_str(&js(inner)),
+            _ => output.push_str(inner),
+        }
+        i = inner_end;
+    }
+    output
+}
+
+/// Minifies every html and css file in the output dir
+/// Returns how much smaller each file was made
+pub fn minify_dir(out: &Path) -> Result<Vec<Saving>, Error> {
+    let mut savings = vec![];
+    for path in files(out, &[])? {
+        let minify = match path.extension().and_then(|e| e.to_str()) {
+            Some("html") => html,
+            Some("css") => css,
+            _ => continue,
+        };
+        let content = fs::read_to_string(&path)?;
+        let minified = minify(&content);
+        fs::write(&path, &minified)?;
+        savings.push(Saving {
+            path: path.strip_prefix(out).unwrap_or(&path).to_path_buf(),
+            before: content.len(),
+            after: minified.len(),
+        });
+    }
+    Ok(savings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bismuth_test::project;
+
+    #[test]
+    fn minify_html() {
+        let content = r#"<!DOCTYPE html>
+<html>
+    <head>
+        <!-- a comment -->
+        <style>
+            body {
+                color: red; /* red */
+            }
+        </style>
+    </head>
+    <body>
+        <p>Some   <b>bold</b>
+            text</p>
+        <a   href="/a b.html"  >link</a>
+        <pre>keep
+    this</pre>
+        <script>
+            // a comment
+            let a = "/* not a comment */"
+            console.log(a)
+        </script>
+    </body>
+</html>
+"#;
+        assert_eq!(
+            html(content),
+            r#"<!DOCTYPE html><html><head><style>body{color:red}</style></head><body><p>Some <b>bold</b> text</p><a href="/a b.html">link</a><pre>keep
+    this</pre><script>let a="/* not a comment */"
+console.log(a)</script></body></html>"#
+        );
+    }
+
+    #[test]
+    fn minify_js() {
+        let content = r#"
+            // a comment
+            const html = `<p>
+                // not a comment
+                ${ items.map(i => `<li>${ i }</li>`).join("") }
+            </p>`;
+            let quote = /["'\/]/g; /* a comment */ let n = a / 2 / b
+            if (a < b && c > d) { i++ } else { return - -x }
+            let s = "a  // b"
+        "#;
+        assert_eq!(
+            js(content),
+            r#"const html=`<p>
+                // not a comment
+                ${items.map(i=>`<li>${i}</li>`).join("")}
+            </p>`;
+let quote=/["'\/]/g;let n=a/2/b
+if(a<b&&c>d){i++}else{return- -x}
+let s="a  // b""#
+        );
+    }
+
+    #[test]
+    fn minify_html_text() {
+        assert_eq!(
+            html("<p>1 < 2 and 3 > 2</p>\n<p>a</p>"),
+            "<p>1 < 2 and 3 > 2</p><p>a</p>"
+        );
+    }
+
+    #[test]
+    fn minify_css() {
+        let content = "a > b,\n.c :hover {\n  margin: 0 auto;\n  content: \"a  b\";\n  width: calc(100% - 2px);\n}\n";
+        assert_eq!(
+            css(content),
+            r#"a>b,.c :hover{margin:0 auto;content:"a  b";width:calc(100% - 2px)}"#
+        );
+    }
+
+    #[test]
+    fn minify_dir_test() {
+        let dir = project(&[
+            ("index.html", "<p>\n    text\n</p>\n"),
+            ("assets/css/base.css", "a {\n    color: red;\n}\n"),
+            ("CNAME", "example.com\n"),
+        ]);
+        let out = dir.path();
+
+        let savings = minify_dir(out).unwrap();
+        assert_eq!(
+            savings,
+            [
+                Saving {
+                    path: PathBuf::from("assets/css/base.css"),
+                    before: 22,
+                    after: 12,
+                },
+                Saving {
+                    path: PathBuf::from("index.html"),
+                    before: 18,
+                    after: 11,
+                },
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("CNAME")).unwrap(),
+            "example.com\n"
+        );
+    }
+}
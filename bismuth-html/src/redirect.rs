@@ -0,0 +1,161 @@
+use bismuth_parser::{
+    url::{self, is_external, resolve, Urls},
+    Parser,
+};
+use html_escape::encode_double_quoted_attribute as escape;
+use serde::Deserialize;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::write::utils::write_html_file;
+use crate::RenderConfig;
+
+const PAGE: &str = include_str!("../data/html/redirect.html");
+
+/// The `[redirects]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RedirectConfig {
+    /// Also writes the aliases to a `_redirects` file, for hosts that support one (ie. Netlify)
+    pub file: bool,
+    /// The status code used in the `_redirects` file
+    pub status: u16,
+}
+
+impl Default for RedirectConfig {
+    fn default() -> Self {
+        Self {
+            file: false,
+            status: 301,
+        }
+    }
+}
+
+/// A old url of a page, that redirects to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alias {
+    /// The markdown file of the page, relative to the src dir
+    pub file: PathBuf,
+    /// The old url, from the root of the site
+    pub from: String,
+    /// The url of the page, from the root of the site
+    pub to: String,
+}
+
+/// Makes a alias from the frontmatter into a url from the root
+/// Aliases are relative to the markdown file, and ones without `.html` are dirs
+/// Ie `old.html` -> `/blogs/old.html`, `/2023/post` -> `/2023/post/`
+fn alias_url(parser: &Parser, alias: &str) -> String {
+    let url = resolve(&url::source(parser), alias.trim());
+    match url.ends_with(".html") || url.ends_with('/') {
+        true => url,
+        false => format!("{url}/"),
+    }
+}
+
+/// Finds the aliases of all the pages
+/// Aliases to other sites can not be made into pages, so they are skipped
+pub fn aliases(parsers: &[Parser], urls: &Urls) -> Vec<Alias> {
+    let mut aliases = vec![];
+    for parser in parsers {
+        let to = urls.path(parser);
+        for alias in parser
+            .metadata
+            .frontmatter
+            .get_aliases()
+            .cloned()
+            .unwrap_or_default()
+        {
+            if alias.trim().is_empty() || is_external(alias.trim()) {
+                continue;
+            }
+            aliases.push(Alias {
+                file: parser.metadata.absolute_path.clone(),
+                from: alias_url(parser, &alias),
+                to: to.clone(),
+            });
+        }
+    }
+    aliases
+}
+
+/// The html of the page that redirects, the url has the base url
+pub fn page(alias: &Alias, config: &RenderConfig) -> String {
+    let urls = &config.urls;
+    let url = urls
+        .full(&alias.to)
+        .unwrap_or_else(|| urls.url(&alias.to, &alias.from));
+    PAGE.replace("{lang}", &config.seo.lang())
+        .replace("{url}", &escape(&url))
+}
+
+/// The `_redirects` file, with a line for each alias
+/// Ie `/docs/old.html /docs/blogs/post.html 301`
+pub fn redirects_file(aliases: &[Alias], status: u16, urls: &Urls) -> String {
+    let base = urls.base_path();
+    aliases
+        .iter()
+        .map(|alias| format!("{base}{} {base}{} {status}\n", alias.from, alias.to))
+        .collect()
+}
+
+/// Writes a page for each alias, and the `_redirects` file if it is enabled
+pub fn write(
+    aliases: &[Alias],
+    redirects: &RedirectConfig,
+    config: &RenderConfig,
+    out: &Path,
+) -> Result<(), Error> {
+    for alias in aliases {
+        write_html_file(&page(alias, config), &alias.from, out)?;
+    }
+    if redirects.file {
+        fs::write(
+            out.join("_redirects"),
+            redirects_file(aliases, redirects.status, &config.urls),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(path: &str, content: &str) -> Parser {
+        let mut parser = Parser::new_test(path, content);
+        parser.parse().unwrap();
+        parser
+    }
+
+    #[test]
+    fn find_aliases() {
+        let parser = parse(
+            "/blogs/post.md",
+            "---\naliases:\n    - old.html\n    - /2023/post\n    - ../posts/post/\n    - https://example.com/post\n---\n# Post\n",
+        );
+        let alias = |from: &str| Alias {
+            file: PathBuf::from("/blogs/post.md"),
+            from: from.to_string(),
+            to: String::from("/blogs/post.html"),
+        };
+        let aliases = aliases(&[parser], &Default::default());
+        assert_eq!(
+            aliases,
+            [
+                alias("/blogs/old.html"),
+                alias("/2023/post/"),
+                alias("/posts/post/"),
+            ]
+        );
+
+        let page = page(&aliases[0], &Default::default());
+        assert!(page.contains(r#"<meta http-equiv="refresh" content="0; url=/blogs/post.html">"#));
+        assert!(page.contains(r#"<link rel="canonical" href="/blogs/post.html">"#));
+        assert_eq!(
+            redirects_file(&aliases[..2], 301, &Default::default()),
+            "/blogs/old.html /blogs/post.html 301\n/2023/post/ /blogs/post.html 301\n"
+        );
+    }
+}
@@ -1,32 +1,315 @@
-use syntect::highlighting::ThemeSet;
-use syntect::html::highlighted_html_for_string;
-use syntect::parsing::SyntaxSet;
+use bismuth_parser::fence::parse_ranges;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{
+    css_for_theme_with_class_style, highlighted_html_for_string, ClassStyle, ClassedHTMLGenerator,
+};
+use syntect::parsing::{SyntaxSet, SyntaxSetBuilder};
+use syntect::util::LinesWithEndings;
 
 use syntect::Error;
 use thiserror::Error;
 
-fn init() -> (SyntaxSet, ThemeSet) {
-    let ps = SyntaxSet::load_defaults_newlines();
-    let ts = ThemeSet::load_defaults();
+/// The name of the stylesheet written in class mode
+pub const STYLESHEET: &str = "highlight";
 
-    (ps, ts)
-}
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
 
 #[derive(Debug, Error)]
 pub enum HighlightError {
     #[error("Internal error {0}")]
     Internal(#[from] Error),
+    #[error("Could not load the theme `{0}`: {1}")]
+    Theme(String, String),
+    #[error("Could not load the syntaxes in `{0}`: {1}")]
+    Syntax(String, String),
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightMode {
+    /// The colors are put in `style` attributes
+    #[default]
+    Inline,
+    /// Uses classes, the colors are put in a stylesheet
+    Class,
+}
+
+/// The `[highlight]` section of `bismuth.toml`
+/// Themes can be a builtin theme, or a path to a `.tmTheme` file relative to the project dir
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HighlightConfig {
+    pub mode: HighlightMode,
+    pub theme: String,
+    /// Only used in class mode, for when the site has a light theme
+    pub light_theme: String,
+    /// A dir of `.sublime-syntax` files that are added to the builtin syntaxes, relative to the project dir
+    pub syntaxes: String,
+    /// Other names for languages, ie. `tf = "terraform"`
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            mode: HighlightMode::Inline,
+            theme: String::from("base16-mocha.dark"),
+            light_theme: String::from("InspiredGitHub"),
+            syntaxes: String::from("syntaxes"),
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+/// The syntaxes and themes, these are loaded once for a build and passed in the `RenderConfig`
+#[derive(Debug)]
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    mode: HighlightMode,
+    theme: Theme,
+    light_theme: Theme,
+    aliases: BTreeMap<String, String>,
+}
+
+fn load_theme(themes: &ThemeSet, name: &str, project: &Path) -> Result<Theme, HighlightError> {
+    if name.ends_with(".tmTheme") {
+        return ThemeSet::get_theme(project.join(name))
+            .map_err(|e| HighlightError::Theme(name.to_string(), e.to_string()));
+    }
+    themes.themes.get(name).cloned().ok_or_else(|| {
+        let builtin = themes.themes.keys().cloned().collect::<Vec<String>>();
+        HighlightError::Theme(
+            name.to_string(),
+            format!("it is not a builtin theme ({})", builtin.join(", ")),
+        )
+    })
+}
+
+/// The builtin syntaxes, along with the ones in `dir` if it exists
+fn load_syntaxes(dir: &Path) -> Result<SyntaxSet, HighlightError> {
+    if !dir.is_dir() {
+        return Ok(SyntaxSet::load_defaults_newlines());
+    }
+    let mut builder: SyntaxSetBuilder = SyntaxSet::load_defaults_newlines().into_builder();
+    builder
+        .add_from_folder(dir, true)
+        .map_err(|e| HighlightError::Syntax(dir.to_string_lossy().to_string(), e.to_string()))?;
+    Ok(builder.build())
+}
+
+impl Highlighter {
+    pub fn new(config: &HighlightConfig, project: &Path) -> Result<Self, HighlightError> {
+        let themes = ThemeSet::load_defaults();
+        Ok(Highlighter {
+            syntaxes: load_syntaxes(&project.join(&config.syntaxes))?,
+            mode: config.mode,
+            theme: load_theme(&themes, &config.theme, project)?,
+            light_theme: load_theme(&themes, &config.light_theme, project)?,
+            aliases: config
+                .aliases
+                .iter()
+                .map(|(k, v)| (k.to_lowercase(), v.clone()))
+                .collect(),
+        })
+    }
+
+    pub fn highlight(&self, lang: &str, code: &str) -> Result<String, HighlightError> {
+        let lang = self
+            .aliases
+            .get(&lang.to_lowercase())
+            .map(String::as_str)
+            .unwrap_or(lang);
+        let syntax = self
+            .syntaxes
+            .find_syntax_by_token(lang)
+            .or_else(|| self.syntaxes.find_syntax_by_name(lang))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+
+        match self.mode {
+            HighlightMode::Inline => {
+                highlighted_html_for_string(code, &self.syntaxes, syntax, &self.theme)
+                    .map_err(HighlightError::Internal)
+            }
+            HighlightMode::Class => {
+                let mut generator =
+                    ClassedHTMLGenerator::new_with_class_style(syntax, &self.syntaxes, CLASS_STYLE);
+                for line in LinesWithEndings::from(code) {
+                    generator.parse_html_for_line_which_includes_newline(line)?;
+                }
+                Ok(format!(
+                    "<pre class=\"hl-code\">\n{}</pre>",
+                    generator.finalize()
+                ))
+            }
+        }
+    }
+
+    pub fn mode(&self) -> HighlightMode {
+        self.mode
+    }
+
+    /// The stylesheet for class mode, `None` if the colors are inline
+    /// The theme that matches the site (`dark`) is used with the `[theme]` colors for the background and text,
+    /// the other theme is used with its own colors when the reader's system prefers it
+    pub fn stylesheet(&self, dark: bool) -> Result<Option<String>, HighlightError> {
+        if self.mode == HighlightMode::Inline {
+            return Ok(None);
+        }
+        let (theme, other, scheme) = match dark {
+            true => (&self.theme, &self.light_theme, "light"),
+            false => (&self.light_theme, &self.theme, "dark"),
+        };
+        let css = css_for_theme_with_class_style(theme, CLASS_STYLE)?;
+        let other = css_for_theme_with_class_style(other, CLASS_STYLE)?;
+
+        Ok(Some(format!(
+            "{css}
+.code, .hl-code {{
+    background-color: var(--background-3);
+}}
+.hl-code {{
+    color: var(--text-1);
+}}
+
+@media (prefers-color-scheme: {scheme}) {{
+{other}}}
+"
+        )))
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Highlighter::new(&HighlightConfig::default(), Path::new(""))
+            .expect("Should be able to load the default themes")
+    }
 }
 
-pub fn highlight(lang: String, code: String) -> Result<String, HighlightError> {
+/// Splits highlighted html into lines
+/// The spans that are open at the end of a line are closed, and opened again on the next line
+fn split_lines(html: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut open: Vec<&str> = vec![];
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        if rest.starts_with('<') {
+            let end = rest.find('>').map(|e| e + 1).unwrap_or(rest.len());
+            let tag = &rest[..end];
+            if tag.starts_with("</span") {
+                open.pop();
+            } else if tag.starts_with("<span") {
+                open.push(tag);
+            }
+            line.push_str(tag);
+            i += end;
+        } else if rest.starts_with('\n') {
+            line.push_str(&"</span>".repeat(open.len()));
+            lines.push(std::mem::replace(&mut line, open.concat()));
+            i += 1;
+        } else {
+            let c = rest.chars().next().expect("Should not be empty");
+            line.push(c);
+            i += c.len_utf8();
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Highlights a code block with `highlighter`, along with the options from the fence
+/// (`title`, `highlight`, `linenos` and `diff` attrs)
+pub fn block(
+    highlighter: &Highlighter,
+    attrs: &HashMap<String, String>,
+    code: String,
+) -> Result<String, HighlightError> {
+    let lang = attrs.get("lang").map(String::as_str).unwrap_or("plaintext");
+    let title = attrs
+        .get("title")
+        .map(|t| {
+            format!(
+                r#"<div class="code-title">{}</div>"#,
+                html_escape::encode_text(t)
+            )
+        })
+        .unwrap_or_default();
+    let ranges = parse_ranges(
+        attrs
+            .get("highlight")
+            .map(String::as_str)
+            .unwrap_or_default(),
+    );
+    let linenos = attrs.contains_key("linenos");
+    let diff = attrs.contains_key("diff");
+
     let code = code.replacen('\n', "", 1);
-    let (ps, ts) = init();
-    let syntax = ps
-        .find_syntax_by_token(&lang)
-        .unwrap_or_else(|| ps.find_syntax_plain_text());
+    if ranges.is_empty() && !linenos && !diff {
+        let html = highlighter.highlight(lang, &code)?;
+        return Ok(format!(
+            r#"<div class="code">{title}<div class="code-inside">{html}"#
+        ));
+    }
+
+    // the markers are taken off, so the code is highlighted as the lang
+    let (markers, lines): (Vec<Option<char>>, Vec<&str>) = code
+        .lines()
+        .map(|line| match line.chars().next() {
+            Some(c @ ('+' | '-')) if diff => (Some(c), &line[1..]),
+            _ => (None, line),
+        })
+        .unzip();
+    let html = highlighter.highlight(lang, &lines.join("\n"))?;
+
+    let start = html.find('>').map(|e| e + 1).unwrap_or_default();
+    let end = html.rfind("</pre>").unwrap_or(html.len());
+    let pre = &html[..start];
+    let inner = html[start..end]
+        .strip_prefix('\n')
+        .unwrap_or(&html[start..end]);
+
+    let lines = split_lines(inner)
+        .into_iter()
+        .zip(markers.into_iter().zip(lines))
+        .enumerate()
+        .map(|(i, (line, (marker, source)))| {
+            let number = i + 1;
+            let mut classes = String::from("line");
+            if ranges.iter().any(|(s, e)| (*s..=*e).contains(&number)) {
+                classes.push_str(" highlighted");
+            }
+            let marker = match marker {
+                Some('+') => {
+                    classes.push_str(" diff-add");
+                    r#"<span class="diff-marker">+</span>"#
+                }
+                Some(_) => {
+                    classes.push_str(" diff-remove");
+                    r#"<span class="diff-marker">-</span>"#
+                }
+                None if diff => r#"<span class="diff-marker"> </span>"#,
+                None => "",
+            };
+            let lineno = match linenos {
+                true => format!(r#"<span class="lineno">{number}</span>"#),
+                false => String::new(),
+            };
+            // so empty lines are not collapsed
+            let line = match source.is_empty() {
+                true => format!("{line} "),
+                false => line,
+            };
+            format!(r#"<span class="{classes}">{lineno}{marker}{line}</span>"#)
+        })
+        .collect::<String>();
 
-    highlighted_html_for_string(&code, &ps, syntax, &ts.themes["base16-mocha.dark"])
-        .map_err(HighlightError::Internal)
+    Ok(format!(
+        r#"<div class="code">{title}<div class="code-inside">{pre}{lines}</pre>"#
+    ))
 }
 
 #[cfg(test)]
@@ -45,15 +328,129 @@ mod test {
 
     #[test]
     fn test() {
-        let code = "fn test() {\n\tlet a = 10;\n}".to_string();
-        let html = highlight("rs".to_string(), code).unwrap();
+        let code = "fn test() {\n\tlet a = 10;\n}";
+        let html = Highlighter::default().highlight("rs", code).unwrap();
         snapshot!(html);
     }
 
     #[test]
     fn test_inline() {
-        let code = "let test = 10".to_string();
-        let html = highlight("rs".to_string(), code).unwrap();
+        let html = Highlighter::default()
+            .highlight("rs", "let test = 10")
+            .unwrap();
+        snapshot!(html);
+    }
+
+    #[test]
+    fn test_class() {
+        let config = HighlightConfig {
+            mode: HighlightMode::Class,
+            ..Default::default()
+        };
+        let highlighter = Highlighter::new(&config, Path::new("")).unwrap();
+        let html = highlighter.highlight("rs", "let test = 10").unwrap();
+        snapshot!(html);
+
+        let css = highlighter.stylesheet(true).unwrap().unwrap();
+        assert!(css.contains(".hl-keyword"));
+        assert!(css.contains("@media (prefers-color-scheme: light)"));
+        assert!(css.find("Mocha").unwrap() < css.find("GitHub").unwrap());
+        let light = highlighter.stylesheet(false).unwrap().unwrap();
+        assert!(light.contains("@media (prefers-color-scheme: dark)"));
+        assert!(light.find("GitHub").unwrap() < light.find("Mocha").unwrap());
+        assert!(Highlighter::new(&Default::default(), Path::new(""))
+            .unwrap()
+            .stylesheet(true)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn custom_syntax() {
+        let dir = bismuth_test::project(&[(
+            "syntaxes/dsl.sublime-syntax",
+            "%YAML 1.2
+---
+name: Dsl
+file_extensions: [dsl]
+scope: source.dsl
+contexts:
+  main:
+    - match: '\\b(rule|when)\\b'
+      scope: keyword.control.dsl
+",
+        )]);
+        let project = dir.path();
+
+        let mut config = HighlightConfig {
+            mode: HighlightMode::Class,
+            ..Default::default()
+        };
+        config
+            .aliases
+            .insert(String::from("Rules"), String::from("dsl"));
+        let highlighter = Highlighter::new(&config, project).unwrap();
+        let keyword = r#"<span class="hl-keyword hl-control hl-dsl">rule</span>"#;
+        assert!(highlighter
+            .highlight("dsl", "rule a")
+            .unwrap()
+            .contains(keyword));
+        assert!(highlighter
+            .highlight("rules", "rule a")
+            .unwrap()
+            .contains(keyword));
+        assert!(highlighter
+            .highlight("Dsl", "rule a")
+            .unwrap()
+            .contains(keyword));
+
+        std::fs::write(project.join("syntaxes/bad.sublime-syntax"), "not: [yaml").unwrap();
+        assert!(matches!(
+            Highlighter::new(&config, project),
+            Err(HighlightError::Syntax(..))
+        ));
+    }
+
+    #[test]
+    fn test_block() {
+        let mut attrs = HashMap::new();
+        attrs.insert(String::from("lang"), String::from("rs"));
+        attrs.insert(String::from("title"), String::from("main.rs"));
+        attrs.insert(String::from("highlight"), String::from("2"));
+        attrs.insert(String::from("linenos"), String::from("true"));
+        attrs.insert(String::from("diff"), String::from("true"));
+        let code = "\nfn test() {\n-\tlet a = 10;\n+\tlet a = 11;\n\n}".to_string();
+        let html = block(&Highlighter::default(), &attrs, code).unwrap();
         snapshot!(html);
     }
+
+    #[test]
+    fn split_highlighted_lines() {
+        let html = "<span a>one\n<span b>two</span>\nthree</span>";
+        assert_eq!(
+            split_lines(html),
+            [
+                "<span a>one</span>",
+                "<span a><span b>two</span></span>",
+                "<span a>three</span>"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_theme() {
+        let config = HighlightConfig {
+            theme: String::from("not a theme"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Highlighter::new(&config, Path::new("")),
+            Err(HighlightError::Theme(..))
+        ));
+        let config = HighlightConfig {
+            theme: String::from("themes/missing.tmTheme"),
+            ..Default::default()
+        };
+        assert!(Highlighter::new(&config, Path::new("")).is_err());
+    }
 }
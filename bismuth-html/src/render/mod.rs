@@ -1,21 +1,36 @@
 use bismuth_parser::{
     tree::{Element, Kind},
+    url::{self, Urls},
     Parser,
 };
 use regex::Regex;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 
-mod code;
-use crate::render::code::highlight;
+pub mod code;
+use crate::image::ImagePipeline;
+use crate::seo::Seo;
 use crate::template::Template;
-use crate::write::{move_assets, utils::write_html_file};
+use crate::write::{move_assets, utils::write_html_file, Dirs};
+use code::{HighlightMode, Highlighter};
 
 const URL_CHECK: &str =
     r"^(http(s)://.)?[-a-zA-Z0-9@:%._\+~#=]{2,256}\.[a-z]{2,6}\b([-a-zA-Z0-9@:%_\+.~#?&//=]*)$";
 
 pub trait Render {
-    fn render(&mut self, path: &Path) -> Option<String>;
+    fn render(&mut self, path: &Path, config: &RenderConfig) -> Option<String>;
+}
+
+/// What is used to render every page of the site
+#[derive(Debug, Default)]
+pub struct RenderConfig {
+    /// Makes the urls of the pages, and the links between them
+    pub urls: Urls,
+    pub seo: Seo,
+    /// Images are only processed if this is set
+    pub images: Option<ImagePipeline>,
+    /// Highlights the code blocks, made from the `[highlight]` config
+    pub highlighter: Highlighter,
 }
 
 #[derive(Clone, Debug)]
@@ -25,26 +40,24 @@ pub struct Renderer {
 
     output: String,
 
-    /// This is the path that the file will be placed to
-    /// Ie `.../output/test/index.html`
+    /// The url of the page, ie `/blogs/post.html`
+    url: String,
+    /// The dir of the url the page is at, links on the page are made from here
+    /// Ie `/blogs/` for `/blogs/post.html`
     path: PathBuf,
 }
 
 impl Renderer {
     /// You should pass parser through bismuth_custom::parse_custom() first
     /// Then put the output into this
-    pub fn new(parser: Parser) -> Self {
-        let path = PathBuf::from(
-            parser
-                .metadata
-                .frontmatter
-                .get_path()
-                .expect("Should have a path"),
-        );
+    pub fn new(parser: Parser, urls: &Urls) -> Self {
+        let url = urls.path(&parser);
+        let path = PathBuf::from(url::dir(&url));
         Self {
             parser,
             asset_list: vec![],
             output: String::new(),
+            url,
             path,
         }
     }
@@ -52,36 +65,27 @@ impl Renderer {
     /// Will move the assets if self.output has stuff in it, and if the asset_list is not empty
     /// Will return Ok(true) if it attempted to move it
     /// Will return Ok(false) if self.output is empty
-    pub fn move_assets(&self) -> Result<bool, std::io::Error> {
+    pub fn move_assets(&self, dirs: &Dirs) -> Result<bool, std::io::Error> {
         if self.output.is_empty() {
             return Ok(false);
         } else if self.asset_list.is_empty() {
             return Ok(true);
         }
-        move_assets(&self.asset_list)?;
+        move_assets(&self.asset_list, dirs)?;
         Ok(true)
     }
 
-    pub fn write(&self) -> Result<(), std::io::Error> {
-        self.move_assets()?;
-        write_html_file(
-            &self.output,
-            &self.path,
-            &self
-                .parser
-                .metadata
-                .frontmatter
-                .get_file_name()
-                .cloned()
-                .unwrap(),
-        )
+    /// Writes the html file to `dirs.out`, and moves the assets it uses
+    pub fn write(&self, dirs: &Dirs) -> Result<(), std::io::Error> {
+        self.move_assets(dirs)?;
+        write_html_file(&self.output, &self.url, &dirs.out)
     }
 }
 
 /// This will set self.output for you
 /// asset_list will be populated with the assets that are needed to be moved
 impl Render for Renderer {
-    fn render(&mut self, _path: &Path) -> Option<String> {
+    fn render(&mut self, _path: &Path, config: &RenderConfig) -> Option<String> {
         let kind = self.parser.metadata.frontmatter.get_kind()?;
 
         let mut values = self
@@ -96,11 +100,28 @@ impl Render for Renderer {
         if let Some(title) = self.parser.metadata.frontmatter.get_title().cloned() {
             values.insert(String::from("title"), title);
         }
+        values
+            .entry(String::from("lang"))
+            .or_insert_with(|| config.seo.lang());
+        values.insert(
+            String::from("seo"),
+            config.seo.head(&self.parser, &config.urls),
+        );
+        let root = config.urls.root(&self.url);
+        let highlight = match config.highlighter.mode() {
+            HighlightMode::Class => format!(
+                r#"<link rel="stylesheet" href="{root}/assets/css/{}.css">"#,
+                code::STYLESHEET
+            ),
+            HighlightMode::Inline => String::new(),
+        };
+        values.insert(String::from("highlight"), highlight);
+        values.insert(String::from("root"), root);
 
         let elements = &self.parser.ast.elements;
         let mut template = Template::new_from_name(kind, &values, None, elements)?;
 
-        self.output = template.render(&self.path)?;
+        self.output = template.render(&self.path, config)?;
 
         // --- HORRIBLE PLS REPLACE WITH GOOD STUFF ---
         // replace all double br's + w/ <double br>
@@ -122,26 +143,50 @@ impl Render for Renderer {
     }
 }
 
+/// A url on the page in the dir `path`, used to make the urls for the page
+fn page_in(path: &Path) -> String {
+    let dir = path.to_string_lossy().replace('\\', "/");
+    format!("{}/index.html", dir.trim_end_matches('/'))
+}
+
 /// Returns (Html, File to move)
-fn handle_file_url(url: &str, text: &str, _path: &Path) -> (String, Option<PathBuf>) {
+fn handle_file_url(
+    url: &str,
+    text: &str,
+    path: &Path,
+    config: &RenderConfig,
+) -> (String, Option<PathBuf>) {
     let valid_url = Regex::new(URL_CHECK).expect("Should be valid regex");
     let video_rg = Regex::new(r"^.+\.(webm|mp4)$").expect("Should be valid regex");
 
-    let begining = if video_rg.is_match(url) {
-        format!(
-            r#"<video src="{url}" controls="controls" muted="muted" class="pt-3" style="max-height:440px;"></video>"#
-        )
-    } else {
-        format!(r#"<img src="{url}" alt="{text}">"#)
-    };
+    let from = page_in(path);
+    let src = config.urls.url(&url::asset(url), &from);
+    if video_rg.is_match(url) {
+        let begining = format!(
+            r#"<video src="{src}" controls="controls" muted="muted" class="pt-3" style="max-height:440px;"></video>"#
+        );
+        if valid_url.is_match(url) {
+            return (begining, None);
+        }
+        return (begining, Some(PathBuf::from(url)));
+    }
+
     if valid_url.is_match(url) {
-        return (begining, None);
+        return (format!(r#"<img src="{url}" alt="{text}">"#), None);
     }
+    let image = config
+        .images
+        .as_ref()
+        .and_then(|images| images.process(url));
+    let begining = match image {
+        Some(image) => image.to_html(&src, text, &from, &config.urls),
+        None => format!(r#"<img src="{src}" alt="{text}" loading="lazy">"#),
+    };
     (begining, Some(PathBuf::from(url)))
 }
 
-/// This will be relitive to the base dir
-fn handle_link(url: &str, text: &str) -> (String, String) {
+/// Links from the root of the site get the base url, or are made relative to the page
+fn handle_link(url: &str, text: &str, path: &Path, urls: &Urls) -> (String, String) {
     let valid_url = Regex::new(URL_CHECK).expect("Should be valid regex");
 
     if valid_url.is_match(url) {
@@ -151,18 +196,22 @@ fn handle_link(url: &str, text: &str) -> (String, String) {
         )
     } else {
         (
-            format!(r#"<a href="{}">{}"#, url, text),
+            format!(r#"<a href="{}">{}"#, urls.url(url, &page_in(path)), text),
             r"</a>".to_string(),
         )
     }
 }
 
 impl Render for Element {
-    fn render(&mut self, path: &Path) -> Option<String> {
+    fn render(&mut self, path: &Path, config: &RenderConfig) -> Option<String> {
         let mut inside = self
             .elements
             .iter()
-            .map(|e| e.clone().render(path).expect("This should not fail"))
+            .map(|e| {
+                e.clone()
+                    .render(path, config)
+                    .expect("This should not fail")
+            })
             .collect::<String>();
 
         // Gets the html of the kind. Some kinds (like Text) may not have a end
@@ -177,11 +226,13 @@ impl Render for Element {
                 if num.parse::<i8>().unwrap_or_default() > 6_i8 {
                     num = String::from("6");
                 }
-                let rg = regex::Regex::new(r"\s+").unwrap();
-                let pre_text = html_escape::encode_text(inside.trim());
-                let text = rg.replace_all(&pre_text, "-").to_lowercase();
+                // set by the parser, so it is unique and has no markup in it
+                let id = match self.get_attr("id") {
+                    Ok(id) => id.clone(),
+                    Err(_) => bismuth_parser::slug::slugify(inside.trim()),
+                };
                 (
-                    format!(r##"<h{num} id="{text}"><a href="#{text}">"##),
+                    format!(r##"<h{num} id="{id}"><a href="#{id}">"##),
                     format!("</a></h{num}>"),
                 )
             }
@@ -190,12 +241,15 @@ impl Render for Element {
             Kind::Link => handle_link(
                 &self.get_attr("link").cloned().unwrap_or_default(),
                 &self.get_text().cloned().unwrap_or_default(),
+                path,
+                &config.urls,
             ),
             Kind::FilePrev => {
                 let (html, asset) = handle_file_url(
                     &self.get_attr("link").cloned().unwrap_or_default(),
                     &self.text.clone().unwrap_or_default(),
                     path,
+                    config,
                 );
                 if let Some(asset) = asset {
                     self.asset_list.push(asset);
@@ -241,16 +295,12 @@ impl Render for Element {
                 String::from("</div>"),
             ),
             Kind::BlockCode => (
-                format!(
-                    r#"<div class="code"><div class="code-inside">{}"#,
-                    highlight(
-                        self.get_attr("lang")
-                            .cloned()
-                            .unwrap_or(String::from("plaintext")),
-                        self.text.clone().unwrap_or_default(),
-                    )
-                    .unwrap()
-                ),
+                code::block(
+                    &config.highlighter,
+                    &self.attrs,
+                    self.text.clone().unwrap_or_default(),
+                )
+                .unwrap(),
                 String::from("</div></div>"),
             ),
 
@@ -287,7 +337,7 @@ impl Render for Element {
                 if let Ok(mut t) = Template::try_from(&self.to_owned()) {
                     // Remove already rendered elements
                     inside = String::new();
-                    let html = t.render(path)?;
+                    let html = t.render(path, config)?;
                     self.asset_list.append(&mut t.asset_list);
                     (html, Default::default())
                 } else {
@@ -310,9 +360,9 @@ mod test {
     fn snapshot(content: &str) -> String {
         let mut parser = Parser::new_test("/test/test.md", content);
         parser.parse().unwrap();
-        let parser = parse_custom(parser, &vec![]);
-        let mut render = Renderer::new(parser);
-        render.render(&PathBuf::new()).unwrap()
+        let parser = parse_custom(parser, &[], &Default::default(), &Default::default());
+        let mut render = Renderer::new(parser, &Default::default());
+        render.render(&PathBuf::new(), &Default::default()).unwrap()
     }
 
     macro_rules! snapshot {
@@ -367,5 +417,10 @@ mod test {
         "this is a test for block latex using katex:\n$$E = mc^2$$"
     );
 
+    snapshot!(
+        test_toc,
+        "%{{\nname: toc\nmin_level: 2\n}}\n# Title\n## One\n### One *A*\n## Two\n## Two\n## 1 < 2 & <i>3</i>"
+    );
+
     snapshot_path!(test_path, "./testdata/test/render/test.md");
 }
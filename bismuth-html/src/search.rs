@@ -0,0 +1,286 @@
+use bismuth_parser::{
+    heading::element_text,
+    tree::{Element, Kind},
+    url::Urls,
+    Parser,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+/// The `[search]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub enabled: bool,
+    pub format: SearchFormat,
+    /// Where the index is written, relative to the output dir
+    pub file: String,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: SearchFormat::default(),
+            file: String::from("search-index.json"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFormat {
+    /// Only the documents
+    #[default]
+    Plain,
+    /// The documents, and a map of each word to the documents it is in
+    Inverted,
+    /// A index that can be loaded with `elasticlunr.Index.load`
+    Elasticlunr,
+}
+
+/// A page in the search index
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Document {
+    pub id: usize,
+    pub title: String,
+    pub url: String,
+    pub headings: Vec<String>,
+    /// The text of the page, without markup, code or headings
+    pub body: String,
+}
+
+fn body_text(elements: &[Element], body: &mut String, headings: &mut Vec<String>) {
+    for element in elements {
+        match element.kind {
+            Kind::Header => {
+                headings.push(element_text(element).trim().to_string());
+                continue;
+            }
+            Kind::CustomElement(_)
+            | Kind::BlockCode
+            | Kind::BlockLaTeX
+            | Kind::InlineLaTeX
+            | Kind::FilePrev => continue,
+            Kind::EndOfLine | Kind::LineBreak => body.push(' '),
+            _ => body.push_str(element.text.as_deref().unwrap_or_default()),
+        }
+        body_text(&element.elements, body, headings);
+    }
+}
+
+/// Makes a document for each page
+pub fn documents(parsers: &[Parser], urls: &Urls) -> Vec<Document> {
+    parsers
+        .iter()
+        .enumerate()
+        .map(|(id, parser)| {
+            let frontmatter = &parser.metadata.frontmatter;
+            let mut body = String::new();
+            let mut headings = vec![];
+            body_text(&parser.ast.elements, &mut body, &mut headings);
+            Document {
+                id,
+                title: frontmatter
+                    .get_title()
+                    .or(frontmatter.get_file_name())
+                    .cloned()
+                    .unwrap_or_default(),
+                url: urls.path(parser),
+                headings,
+                body: body.split_whitespace().collect::<Vec<&str>>().join(" "),
+            }
+        })
+        .collect()
+}
+
+/// Lowercase words, words with one letter are skipped
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn counts(text: &str) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for token in tokens(text) {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn inverted(documents: &[Document]) -> Value {
+    let mut index: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+    for document in documents {
+        let text = format!(
+            "{} {} {}",
+            document.title,
+            document.headings.join(" "),
+            document.body
+        );
+        for (token, count) in counts(&text) {
+            index.entry(token).or_default().push((document.id, count));
+        }
+    }
+    json!({ "documents": documents, "index": index })
+}
+
+/// A node in the trie that elasticlunr stores its index in
+#[derive(Default)]
+struct Node {
+    docs: BTreeMap<usize, f64>,
+    children: BTreeMap<char, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, token: &str, id: usize, tf: f64) {
+        let mut node = self;
+        for c in token.chars() {
+            node = node.children.entry(c).or_default();
+        }
+        node.docs.insert(id, tf);
+    }
+
+    fn to_value(&self) -> Value {
+        let mut map = Map::new();
+        let docs = self
+            .docs
+            .iter()
+            .map(|(id, tf)| (id.to_string(), json!({ "tf": tf })))
+            .collect::<Map<String, Value>>();
+        map.insert(String::from("df"), json!(self.docs.len()));
+        map.insert(String::from("docs"), Value::Object(docs));
+        for (c, node) in &self.children {
+            map.insert(c.to_string(), node.to_value());
+        }
+        Value::Object(map)
+    }
+}
+
+/// The same format as `elasticlunr.Index.prototype.toJSON`
+/// Words are not stemmed, so the pipeline only has the trimmer
+fn elasticlunr(documents: &[Document]) -> Value {
+    let fields = ["title", "headings", "body"];
+    let mut docs = Map::new();
+    let mut doc_info = Map::new();
+    let mut index = Map::new();
+    let mut roots = fields.map(|_| Node::default());
+
+    for document in documents {
+        let id = document.id.to_string();
+        let values = [
+            document.title.clone(),
+            document.headings.join(" "),
+            document.body.clone(),
+        ];
+        let mut info = Map::new();
+        for ((field, text), root) in fields.iter().zip(&values).zip(&mut roots) {
+            let counts = counts(text);
+            info.insert(field.to_string(), json!(counts.values().sum::<usize>()));
+            for (token, count) in counts {
+                root.insert(&token, document.id, (count as f64).sqrt());
+            }
+        }
+        doc_info.insert(id.clone(), Value::Object(info));
+        docs.insert(
+            id,
+            json!({
+                "id": document.id,
+                "title": document.title,
+                "url": document.url,
+                "headings": values[1],
+                "body": document.body,
+            }),
+        );
+    }
+    for (field, root) in fields.iter().zip(&roots) {
+        index.insert(field.to_string(), json!({ "root": root.to_value() }));
+    }
+
+    json!({
+        "version": "0.9.5",
+        "fields": fields,
+        "ref": "id",
+        "documentStore": {
+            "docs": docs,
+            "docInfo": doc_info,
+            "length": documents.len(),
+            "save": true,
+        },
+        "index": index,
+        "pipeline": ["trimmer"],
+    })
+}
+
+/// Makes the search index in the format from the config
+pub fn index(documents: &[Document], format: SearchFormat) -> String {
+    let value = match format {
+        SearchFormat::Plain => json!({ "documents": documents }),
+        SearchFormat::Inverted => inverted(documents),
+        SearchFormat::Elasticlunr => elasticlunr(documents),
+    };
+    value.to_string()
+}
+
+/// Writes the search index to the output dir
+pub fn write_index(documents: &[Document], config: &SearchConfig, out: &Path) -> Result<(), Error> {
+    let path = out.join(config.file.trim_start_matches('/'));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, index(documents, config.format))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn documents_for(content: &str) -> Vec<Document> {
+        let mut parser = Parser::new_test("/blogs/post.md", content);
+        parser.parse().unwrap();
+        documents(&[parser], &Default::default())
+    }
+
+    #[test]
+    fn document() {
+        let documents = documents_for(
+            "---\ntitle: A post\n---\n# Setup\nInstall **rust** with [rustup](https://rustup.rs)\n\n```sh\ncurl x\n```\n## Usage\nRun it\n",
+        );
+        assert_eq!(
+            documents,
+            [Document {
+                id: 0,
+                title: String::from("A post"),
+                url: String::from("/blogs/post.html"),
+                headings: vec![String::from("Setup"), String::from("Usage")],
+                body: String::from("Install rust with rustup Run it"),
+            }]
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let documents = documents_for("---\ntitle: Rust\n---\nrust is a language\n");
+
+        let plain: Value = serde_json::from_str(&index(&documents, SearchFormat::Plain)).unwrap();
+        assert_eq!(plain["documents"][0]["title"], "Rust");
+
+        let inverted: Value =
+            serde_json::from_str(&index(&documents, SearchFormat::Inverted)).unwrap();
+        assert_eq!(inverted["index"]["rust"], json!([[0, 2]]));
+        assert_eq!(inverted["index"].get("a"), None);
+
+        let lunr: Value =
+            serde_json::from_str(&index(&documents, SearchFormat::Elasticlunr)).unwrap();
+        assert_eq!(lunr["ref"], "id");
+        assert_eq!(lunr["documentStore"]["docInfo"]["0"]["body"], 3);
+        let node = &lunr["index"]["body"]["root"]["r"]["u"]["s"]["t"];
+        assert_eq!(node["df"], 1);
+        assert_eq!(node["docs"]["0"]["tf"], 1.0);
+    }
+}
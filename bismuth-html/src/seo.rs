@@ -0,0 +1,360 @@
+use bismuth_parser::{
+    date::parse_date,
+    heading::element_text,
+    tree::{Element, Kind},
+    url::{self, is_external, Urls},
+    Parser,
+};
+use html_escape::encode_double_quoted_attribute as escape;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Descriptions made from the first paragraph are cut to this many chars
+const DESCRIPTION_LEN: usize = 160;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The `[seo]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SeoConfig {
+    /// The `lang` of every page, pages can change it with the `lang` value
+    pub lang: String,
+    /// Used for pages that do not have a description or a paragraph
+    pub description: Option<String>,
+    /// Used for pages that do not have an image
+    pub image: Option<String>,
+    /// The twitter handle of the site, ie. `@bismuth`
+    pub twitter: Option<String>,
+    /// The author of the articles
+    pub author: Option<String>,
+}
+
+impl Default for SeoConfig {
+    fn default() -> Self {
+        Self {
+            lang: String::from("en"),
+            description: None,
+            image: None,
+            twitter: None,
+            author: None,
+        }
+    }
+}
+
+/// Makes the metadata of every page
+#[derive(Clone, Debug, Default)]
+pub struct Seo {
+    config: SeoConfig,
+    /// The name of the website
+    name: String,
+}
+
+/// The text of the first paragraph, up to the first blank line
+fn first_paragraph(elements: &[Element]) -> Option<String> {
+    let mut text = String::new();
+    let mut new_lines = 0;
+    for element in elements {
+        match element.kind {
+            Kind::Paragraph => {
+                text.push_str(&element_text(element));
+                new_lines = 0;
+            }
+            Kind::EndOfLine | Kind::LineBreak => {
+                new_lines += 1;
+                if new_lines > 1 && !text.trim().is_empty() {
+                    break;
+                }
+                text.push(' ');
+            }
+            _ if text.trim().is_empty() => new_lines = 0,
+            _ => break,
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    match text.is_empty() {
+        true => None,
+        false => Some(text),
+    }
+}
+
+/// Cuts the description at a word, so it is not too long for search results
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= DESCRIPTION_LEN {
+        return text.to_string();
+    }
+    let mut short = String::new();
+    for word in text.split(' ') {
+        if short.chars().count() + word.chars().count() + 1 > DESCRIPTION_LEN {
+            break;
+        }
+        if !short.is_empty() {
+            short.push(' ');
+        }
+        short.push_str(word);
+    }
+    format!("{short}...")
+}
+
+fn first_image(elements: &[Element]) -> Option<String> {
+    let image =
+        regex::Regex::new(r"(?i)\.(png|jpe?g|gif|webp|avif|svg)$").expect("Should be valid regex");
+    for element in elements {
+        if let (Kind::FilePrev, Ok(link)) = (&element.kind, element.get_attr("link")) {
+            if image.is_match(link.trim()) {
+                return Some(link.trim().to_string());
+            }
+        }
+        if let Some(link) = first_image(&element.elements) {
+            return Some(link);
+        }
+    }
+    None
+}
+
+/// Makes a url from the root absolute, if the base url has the domain
+fn absolute(urls: &Urls, path: &str, page: &str) -> String {
+    urls.full(path).unwrap_or_else(|| urls.url(path, page))
+}
+
+impl Seo {
+    pub fn new(config: SeoConfig, name: &str) -> Self {
+        Self {
+            config,
+            name: name.to_string(),
+        }
+    }
+
+    /// The `lang` of a page
+    pub fn lang(&self) -> String {
+        self.config.lang.clone()
+    }
+
+    fn description(&self, parser: &Parser) -> Option<String> {
+        parser
+            .metadata
+            .frontmatter
+            .get_description()
+            .cloned()
+            .or_else(|| first_paragraph(&parser.ast.elements).map(|d| shorten(&d)))
+            .or_else(|| self.config.description.clone())
+    }
+
+    /// Images are from the project dir, like the images in the page
+    fn image(&self, parser: &Parser, urls: &Urls, page: &str) -> Option<String> {
+        let image = parser
+            .metadata
+            .frontmatter
+            .get_image()
+            .cloned()
+            .or_else(|| first_image(&parser.ast.elements))
+            .or_else(|| self.config.image.clone())?;
+        match is_external(&image) {
+            true => Some(image),
+            false => Some(absolute(urls, &url::asset(&image), page)),
+        }
+    }
+
+    /// Pages with a `date` are articles
+    fn article(
+        &self,
+        parser: &Parser,
+        title: &str,
+        description: &Option<String>,
+        image: &Option<String>,
+        url: &str,
+    ) -> Option<String> {
+        let date = parser.metadata.frontmatter.get_value("date")?;
+
+        let mut article = Map::new();
+        article.insert(String::from("@context"), json!("https://schema.org"));
+        article.insert(String::from("@type"), json!("Article"));
+        article.insert(String::from("headline"), json!(title));
+        if let Some(description) = description {
+            article.insert(String::from("description"), json!(description));
+        }
+        if let Some(image) = image {
+            article.insert(String::from("image"), json!(image));
+        }
+        // dates that can not be parsed are left out, they would not be valid
+        if let Some(date) = parse_date(date) {
+            article.insert(
+                String::from("datePublished"),
+                json!(date.format(DATE_FORMAT).to_string()),
+            );
+        }
+        if let Some(modified) = parser.metadata.modified {
+            article.insert(
+                String::from("dateModified"),
+                json!(modified.format(DATE_FORMAT).to_string()),
+            );
+        }
+        if let Some(author) = &self.config.author {
+            article.insert(
+                String::from("author"),
+                json!({ "@type": "Person", "name": author }),
+            );
+        }
+        if !self.name.is_empty() {
+            article.insert(
+                String::from("publisher"),
+                json!({ "@type": "Organization", "name": self.name }),
+            );
+        }
+        article.insert(String::from("mainEntityOfPage"), json!(url));
+
+        // so the text can not close the script
+        let json = Value::Object(article).to_string().replace("</", "<\\/");
+        Some(format!(
+            r#"<script type="application/ld+json">{json}</script>"#
+        ))
+    }
+
+    /// The description, canonical url, OpenGraph and Twitter tags of a page
+    /// Articles (pages with a `date`) also get JSON-LD `Article` data
+    pub fn head(&self, parser: &Parser, urls: &Urls) -> String {
+        let frontmatter = &parser.metadata.frontmatter;
+        let page = urls.path(parser);
+        let canonical = urls.full(&page);
+        let url = absolute(urls, &page, &page);
+        let title = frontmatter
+            .get_title()
+            .or(frontmatter.get_file_name())
+            .cloned()
+            .unwrap_or_default();
+        let description = self.description(parser);
+        let image = self.image(parser, urls, &page);
+        let article = self.article(parser, &title, &description, &image, &url);
+
+        let mut tags = vec![];
+        let mut meta = |attr: &str, key: &str, value: &str| {
+            tags.push(format!(
+                r#"<meta {attr}="{key}" content="{}">"#,
+                escape(value)
+            ));
+        };
+        if let Some(description) = &description {
+            meta("name", "description", description);
+        }
+        meta(
+            "property",
+            "og:type",
+            match article {
+                Some(_) => "article",
+                None => "website",
+            },
+        );
+        meta("property", "og:title", &title);
+        if let Some(description) = &description {
+            meta("property", "og:description", description);
+        }
+        if let Some(canonical) = &canonical {
+            meta("property", "og:url", canonical);
+        }
+        if let Some(image) = &image {
+            meta("property", "og:image", image);
+        }
+        if !self.name.is_empty() {
+            meta("property", "og:site_name", &self.name);
+        }
+        meta(
+            "name",
+            "twitter:card",
+            match image {
+                Some(_) => "summary_large_image",
+                None => "summary",
+            },
+        );
+        if let Some(twitter) = &self.config.twitter {
+            meta("name", "twitter:site", twitter);
+        }
+        meta("name", "twitter:title", &title);
+        if let Some(description) = &description {
+            meta("name", "twitter:description", description);
+        }
+        if let Some(image) = &image {
+            meta("name", "twitter:image", image);
+        }
+
+        if let Some(canonical) = &canonical {
+            tags.insert(
+                0,
+                format!(r#"<link rel="canonical" href="{}">"#, escape(canonical)),
+            );
+        }
+        tags.extend(article);
+        tags.join("\n    ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    macro_rules! snapshot {
+        ($content:expr) => {
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("../testdata/output/seo/");
+            settings.bind(|| {
+                insta::assert_snapshot!($content);
+            });
+        };
+    }
+
+    fn parse(content: &str) -> Parser {
+        let mut parser = Parser::new_test("/blogs/post.md", content);
+        parser.parse().unwrap();
+        parser
+    }
+
+    fn site() -> Seo {
+        Seo::new(
+            SeoConfig {
+                twitter: Some(String::from("@bismuth")),
+                author: Some(String::from("Oreo")),
+                image: Some(String::from("card.png")),
+                ..Default::default()
+            },
+            "Bismuth",
+        )
+    }
+
+    fn urls() -> Urls {
+        Urls::new("https://example.com/", Default::default())
+    }
+
+    #[test]
+    fn fallbacks() {
+        let parser = parse(
+            "---\ntitle: A \"post\"\nvalues:\n    - date: 2023-03-10\n---\n# Intro\nSome **bold** text\nand more.\n\nNot this\n![cat](images/cat.png)\n",
+        );
+        let seo = site();
+        assert_eq!(
+            seo.description(&parser),
+            Some(String::from("Some bold text and more."))
+        );
+        assert_eq!(
+            seo.image(&parser, &urls(), "/blogs/post.html"),
+            Some(String::from("https://example.com/images/cat.png"))
+        );
+        assert_eq!(
+            seo.image(&parse("# Intro\n"), &urls(), "/blogs/post.html"),
+            Some(String::from("https://example.com/card.png"))
+        );
+        snapshot!(seo.head(&parser, &urls()));
+    }
+
+    #[test]
+    fn page() {
+        let parser = parse(
+            "---\ntitle: About\ndescription: All about <me>\nimage: https://example.com/me.png\n---\nHello\n",
+        );
+        let seo = Seo::default();
+        snapshot!(seo.head(&parser, &Default::default()));
+
+        let long = "word ".repeat(100);
+        let short = shorten(long.trim());
+        assert!(short.chars().count() <= DESCRIPTION_LEN + 3);
+        assert!(short.ends_with("word..."));
+    }
+}
@@ -1,2 +1,4 @@
 pub const TEST: &str = "Test template: {value_1} {value_2} {elements}";
 pub const DEFAULT: &str = include_str!("../../data/html/default.html");
+/// The taxonomy pages use the default template, with the title above the elements
+pub const TAXONOMY_TITLE: &str = r#"<h1 class="taxonomy-title">{title}</h1>"#;
@@ -1,10 +1,10 @@
 use bismuth_parser::tree::{Element, Kind};
-use regex::Regex;
+use regex::{NoExpand, Regex};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 
-use crate::render::Render;
+use crate::render::{Render, RenderConfig};
 
 pub mod builtin;
 
@@ -67,6 +67,10 @@ impl<'a> Template<'a> {
         match name.to_lowercase().as_str() {
             "test" => Some(builtin::TEST.to_string()),
             "default" => Some(builtin::DEFAULT.replace('\r', "")),
+            "taxonomy" => Some(builtin::DEFAULT.replace('\r', "").replace(
+                "{elements}",
+                &format!("{}\n            {{elements}}", builtin::TAXONOMY_TITLE),
+            )),
             _ => None,
         }
     }
@@ -84,7 +88,7 @@ impl<'a> Template<'a> {
 }
 
 impl Render for Template<'_> {
-    fn render(&mut self, path: &Path) -> Option<String> {
+    fn render(&mut self, path: &Path, config: &RenderConfig) -> Option<String> {
         let mut output = self.template.to_string();
         // First replace {elements} w/ rendered elements
         let elements_str = self
@@ -92,24 +96,27 @@ impl Render for Template<'_> {
             .iter()
             .map(|e| {
                 let mut element = e.clone();
-                let html = element.render(path).expect("Should not fail");
+                let html = element.render(path, config).expect("Should not fail");
                 self.asset_list.append(&mut element.asset_list);
                 html
             })
             .collect::<String>();
         let e_rg = Regex::new(r"\{(?i)elements\}").expect("Should be valid regex");
-        output = e_rg.replace_all(&output, elements_str).to_string();
+        output = e_rg
+            .replace_all(&output, NoExpand(&elements_str))
+            .to_string();
 
         // Next do the body
         let b_rg = Regex::new(r"\{(?i)body\}").expect("Should be valid regex");
         output = b_rg
-            .replace_all(&output, self.body.cloned().unwrap_or_default())
+            .replace_all(&output, NoExpand(self.body.map_or("", |b| b.as_str())))
             .to_string();
 
-        // next do each value
+        // next do each value, `$` in them is text and not a capture group
         for (key, value) in self.values {
-            let rg = Regex::new(&format!(r"\{{(?i){}\}}", key)).expect("Should be valid");
-            output = rg.replace_all(&output, value).to_string();
+            let rg =
+                Regex::new(&format!(r"\{{(?i){}\}}", regex::escape(key))).expect("Should be valid");
+            output = rg.replace_all(&output, NoExpand(value)).to_string();
         }
         Some(output)
     }
@@ -178,7 +185,9 @@ mod test {
         );
         let mut template = init_template!(parser, String::from("test:\n {elements}"), None);
 
-        let s = template.render(&PathBuf::new()).unwrap();
+        let s = template
+            .render(&PathBuf::new(), &Default::default())
+            .unwrap();
         snapshot!(s);
     }
 
@@ -194,10 +203,34 @@ mod test {
         );
         let mut template = init_template!(parser, String::from("test:\n {elements}"), None);
 
-        let s = template.render(&PathBuf::new()).unwrap();
+        let s = template
+            .render(&PathBuf::new(), &Default::default())
+            .unwrap();
         snapshot!(s);
     }
 
+    #[test]
+    fn dollar_signs() {
+        let parser = init_parser(
+            "text",
+            "
+            values:
+                - price: $1 or ${price}
+            ",
+        );
+        let body = String::from("$$ body");
+        let mut template = init_template!(
+            parser,
+            String::from("{price} {body} {elements}"),
+            Some(&body)
+        );
+
+        let s = template
+            .render(&PathBuf::new(), &Default::default())
+            .unwrap();
+        assert_eq!(s, "$1 or ${price} $$ body <p>text</p>\n<br>\n");
+    }
+
     #[test]
     fn test_2() {
         let parser = init_parser(
@@ -212,7 +245,9 @@ mod test {
         let name = parser.metadata.frontmatter.get_kind().unwrap();
         let mut template = init_template_name!(parser, name, None);
 
-        let s = template.render(&PathBuf::new()).unwrap();
+        let s = template
+            .render(&PathBuf::new(), &Default::default())
+            .unwrap();
         snapshot!(s);
     }
 }
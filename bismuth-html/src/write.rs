@@ -1,74 +1,225 @@
-use std::io::Error;
-use std::path::PathBuf;
+use bismuth_md::is_markdown;
+use bismuth_parser::url;
+use glob::Pattern;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
 // use crate::render::Renderer;
 
-const BUILD: &str = "./build";
-const BUILD_ASSETS: &str = "./build/assets";
-const BUILD_ASSETS_CSS: &str = "./build/assets/css";
-const ASSETS: &str = "./assets";
+/// The assets folder inside of the output dir
+pub(crate) const BUILD_ASSETS: &str = "assets";
+const BUILD_ASSETS_CSS: &str = "assets/css";
 
 const CSS: &[(&str, &str)] = &[(include_str!("../data/css/base.css"), "base")];
 
+/// The directories that are used when writing the site
+/// Relative paths are relative to the current dir, so they should be joined onto the project dir first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dirs {
+    /// The project dir, paths to assets are relative to this
+    pub project: PathBuf,
+    /// Where the site is written to, `build/` by default
+    pub out: PathBuf,
+    /// Where the markdown files are loaded from, `src/` by default
+    pub src: PathBuf,
+    /// Where the assets are kept, `assets/` by default
+    pub assets: PathBuf,
+    /// Files that are copied to the root of the site as is, `static/` by default
+    pub static_files: PathBuf,
+}
+
+impl Dirs {
+    /// Makes the dirs, all of them are relative to `project`
+    pub fn new(project: &Path, src: &str, out: &str, assets: &str, static_files: &str) -> Self {
+        Dirs {
+            project: project.to_path_buf(),
+            out: project.join(out),
+            src: project.join(src),
+            assets: project.join(assets),
+            static_files: project.join(static_files),
+        }
+    }
+
+    /// Checks that building will not remove the project, ie. if `out` is `""`, `"."` or `"src"`
+    /// The out dir is removed before each build, so it can not be or have the other dirs in it
+    pub fn check(&self) -> Result<(), String> {
+        let out = normalize(&self.out);
+        for (name, dir) in [
+            ("project", &self.project),
+            ("src", &self.src),
+            ("assets", &self.assets),
+            ("static", &self.static_files),
+        ] {
+            if normalize(dir).starts_with(&out) {
+                return Err(format!(
+                    "The out dir `{}` can not be or have the {name} dir in it, it is removed when building",
+                    self.out.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the assets dir is put in the output, ie. `assets/`
+    /// If it is not in the project it is put at `assets/`
+    pub fn assets_out(&self) -> PathBuf {
+        normalize(&self.assets)
+            .strip_prefix(normalize(&self.project))
+            .unwrap_or(Path::new(BUILD_ASSETS))
+            .to_path_buf()
+    }
+
+    /// The file a local url is copied from, urls are from the project dir
+    /// Urls in the assets dir are from where the assets dir is, so it can be set in `bismuth.toml`
+    pub fn source(&self, url: &Path) -> PathBuf {
+        let url = url.strip_prefix("/").unwrap_or(url);
+        match url.strip_prefix(self.assets_out()) {
+            Ok(path) => self.assets.join(path),
+            Err(_) => self.project.join(url),
+        }
+    }
+}
+
+/// Removes the `.` and `..` in a path, without needing it to exist
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            c => normal.push(c),
+        }
+    }
+    normal
+}
+
+/// Copies every file in `from` to the same place in `to`
+/// Files that match `skip`, or one of the `ignore` patterns are not copied
+/// The patterns are matched against the path relative to `from` and the file name
+/// Returns how many files were copied
+pub fn copy_dir(
+    from: &Path,
+    to: &Path,
+    ignore: &[Pattern],
+    skip: &dyn Fn(&Path) -> bool,
+) -> Result<usize, Error> {
+    let mut copied = 0;
+    for path in utils::files(from, ignore)? {
+        if skip(&path) {
+            continue;
+        }
+        let rel = path.strip_prefix(from).expect("Should be inside of from");
+        let new_path = to.join(rel);
+        if let Some(dir) = new_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::copy(&path, new_path)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Copies the files that are not rendered into the output dir
+/// - The assets dir is copied to the same place in the output dir (ie. `build/assets/`)
+/// - The static dir is copied to the root of the output dir
+/// - Files in the src dir that are not markdown are copied next to their pages
+///
+/// Returns how many files were copied
+pub fn copy_static(dirs: &Dirs, ignore: &[String]) -> Result<usize, Error> {
+    let ignore = ignore
+        .iter()
+        .map(|p| {
+            Pattern::new(p).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid ignore pattern `{p}`: {e}"),
+                )
+            })
+        })
+        .collect::<Result<Vec<Pattern>, Error>>()?;
+
+    let assets_out = dirs.out.join(dirs.assets_out());
+
+    let mut copied = copy_dir(&dirs.assets, &assets_out, &ignore, &|_| false)?;
+    copied += copy_dir(&dirs.static_files, &dirs.out, &ignore, &|_| false)?;
+    copied += copy_dir(&dirs.src, &dirs.out, &ignore, &is_markdown)?;
+    Ok(copied)
+}
+
 pub mod utils {
     use super::*;
-    use std::{
-        fs,
-        io::Error,
-        path::{Path, PathBuf},
-    };
-
-    /// Makes the `./build/` folder along with the `./bulid/assets/` folder
-    pub fn make_build() -> Result<(), Error> {
-        let build = Path::new(BUILD);
-        if !build.exists() {
-            fs::create_dir_all(build)?
+    use std::{fs, io::Error, path::Path};
+
+    /// Gets every file in `dir` and the dirs in it, sorted so the order is the same on every build
+    /// Files and dirs that match one of the `ignore` patterns are left out, by the path relative to `dir` or the name
+    /// A dir that does not exist has no files
+    pub fn files(dir: &Path, ignore: &[Pattern]) -> Result<Vec<PathBuf>, Error> {
+        let mut files = vec![];
+        if !dir.is_dir() {
+            return Ok(files);
         }
-        make_build_assets()?;
-        Ok(())
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(current) = dirs.pop() {
+            for entry in fs::read_dir(current)? {
+                let path = entry?.path();
+                let rel = path.strip_prefix(dir).expect("Should be inside of dir");
+                let name = rel.file_name().unwrap_or_default().to_string_lossy();
+                if ignore
+                    .iter()
+                    .any(|p| p.matches_path(rel) || p.matches(&name))
+                {
+                    continue;
+                }
+
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
     }
 
-    /// Makes the `./bulid/assets/` folder
-    /// Will make the `./build/` folder if it does not exitst
-    /// But if it does not, you *should* call `make_build()`
-    pub fn make_build_assets() -> Result<(), Error> {
-        let assets = Path::new(BUILD_ASSETS);
-        if !assets.exists() {
-            fs::create_dir_all(assets)?
+    /// Makes the `build/` folder along with the `bulid/assets/` folder
+    pub fn make_build(out: &Path) -> Result<(), Error> {
+        if !out.exists() {
+            fs::create_dir_all(out)?
         }
+        make_build_assets(out)?;
         Ok(())
     }
 
-    /// Makes the `./assets/` folder
-    pub fn make_assets() -> Result<(), Error> {
-        let assets = Path::new(ASSETS);
+    /// Makes the `bulid/assets/` folder
+    /// Will make the `build/` folder if it does not exitst
+    /// But if it does not, you *should* call `make_build()`
+    pub fn make_build_assets(out: &Path) -> Result<(), Error> {
+        let assets = out.join(BUILD_ASSETS);
         if !assets.exists() {
             fs::create_dir_all(assets)?
         }
         Ok(())
     }
 
-    pub fn make_css() -> Result<(), Error> {
-        make_build_assets()?;
+    pub fn make_css(out: &Path) -> Result<(), Error> {
+        make_build_assets(out)?;
 
-        let css = Path::new(BUILD_ASSETS_CSS);
+        let css = out.join(BUILD_ASSETS_CSS);
         if !css.exists() {
             fs::create_dir_all(css)?
         }
         Ok(())
     }
 
-    /// Writes a html file to the `./build/` folder
-    /// The path should not have a . in the begining
-    pub fn write_html_file(content: &str, path: &Path, name: &String) -> Result<(), Error> {
+    /// Writes a html file to the `build/` folder, at the url of the page
+    /// Ie `/blogs/post.html` -> `build/blogs/post.html`, `/blogs/post/` -> `build/blogs/post/index.html`
+    pub fn write_html_file(content: &str, url: &str, out: &Path) -> Result<(), Error> {
         // Makes build dir if it does not exitst
-        make_build()?;
-        let mut path_str = path.to_string_lossy().to_string();
-        path_str.remove(0);
-        let path = &mut PathBuf::from(path_str);
-
-        let path_build = Path::new(BUILD);
-        let path = path.join(format!("{}.html", name));
-        let full_path = path_build.join(path);
+        make_build(out)?;
+        let full_path = out.join(url::html_file(url).trim_start_matches('/'));
 
         let mut dir = full_path.clone();
         dir.pop();
@@ -77,37 +228,107 @@ pub mod utils {
         fs::write(full_path, content)
     }
 
-    /// Moves a asset from the `./assets/` folder to the `./bulid/assets/` folder
+    /// Moves a asset from the project to the same place in the `bulid/` folder
+    /// Ie `assets/image.png` -> `build/assets/image.png`
     /// The path should not have a . in the begining
-    pub fn move_asset(path: &Path) -> Result<(), Error> {
-        // Makes both the `./assets/` folder and the `./build/assets/` folder if the do not exitst
-        make_assets()?;
-        make_build_assets()?;
-
-        let new_path = Path::new(BUILD).join(path);
-        let old_full = Path::new("./").canonicalize()?.join(path);
+    pub fn move_asset(path: &Path, dirs: &Dirs) -> Result<(), Error> {
+        let path = path.strip_prefix("/").unwrap_or(path);
+        let new_path = dirs.out.join(path);
+        let old_full = dirs.source(path);
+        if !old_full.is_file() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("the file `{}` does not exist", old_full.display()),
+            ));
+        }
 
+        if let Some(dir) = new_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
         fs::copy(old_full, new_path).map(|_| ())
     }
 
-    pub fn write_css(content: &str, name: &str) -> Result<(), Error> {
-        make_css()?;
+    pub fn write_css(content: &str, name: &str, out: &Path) -> Result<(), Error> {
+        make_css(out)?;
 
-        let path = PathBuf::from(format!("{BUILD_ASSETS_CSS}/{name}.css"));
+        let path = out.join(BUILD_ASSETS_CSS).join(format!("{name}.css"));
         fs::write(path, content)
     }
 }
 
-pub fn move_css_folder() -> Result<(), Error> {
+pub fn move_css_folder(out: &Path) -> Result<(), Error> {
     for (css, name) in CSS {
-        utils::write_css(css, name)?;
+        utils::write_css(css, name, out)?;
     }
     Ok(())
 }
 
-pub fn move_assets(assets: &[PathBuf]) -> Result<(), Error> {
+pub fn move_assets(assets: &[PathBuf], dirs: &Dirs) -> Result<(), Error> {
     for asset in assets {
-        utils::move_asset(asset)?;
+        utils::move_asset(asset, dirs)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bismuth_test::project;
+
+    #[test]
+    fn copy_static_test() {
+        let project = project(&[
+            ("assets/fonts/font.woff2", "font"),
+            ("assets/notes.tmp", "notes"),
+            ("static/CNAME", "example.com"),
+            ("static/favicon.ico", "icon"),
+            ("src/index.md", "# Index"),
+            ("src/blog/post.md", "# Post"),
+            ("src/blog/image.png", "png"),
+        ]);
+
+        let dirs = Dirs::new(project.path(), "src", "build", "assets", "static");
+        let copied = copy_static(&dirs, &[String::from("*.tmp")]).unwrap();
+        assert_eq!(copied, 4);
+
+        let out = &dirs.out;
+        assert!(out.join("assets/fonts/font.woff2").exists());
+        assert!(!out.join("assets/notes.tmp").exists());
+        assert!(out.join("CNAME").exists());
+        assert!(out.join("favicon.ico").exists());
+        assert!(out.join("blog/image.png").exists());
+        assert!(!out.join("blog/post.md").exists());
+    }
+
+    #[test]
+    fn check_dirs() {
+        let project = Path::new("/site");
+        let dirs = |out: &str| Dirs::new(project, "src", out, "assets", "static").check();
+        assert!(dirs("build").is_ok());
+        assert!(dirs("../site-build").is_ok());
+        for out in ["", ".", "src", "src/..", "assets", "..", "/"] {
+            assert!(dirs(out).is_err(), "`{out}` should not be allowed");
+        }
+    }
+
+    #[test]
+    fn asset_source() {
+        let project = Path::new("/site");
+        let dirs = Dirs::new(project, "src", "build", "../shared", "static");
+        assert_eq!(dirs.assets_out(), Path::new("assets"));
+        assert_eq!(
+            dirs.source(Path::new("/assets/cat.png")),
+            Path::new("/site/../shared/cat.png")
+        );
+        assert_eq!(
+            dirs.source(Path::new("images/cat.png")),
+            Path::new("/site/images/cat.png")
+        );
+
+        let dirs = Dirs::new(project, "src", "build", "media", "static");
+        assert_eq!(
+            dirs.source(Path::new("/media/cat.png")),
+            Path::new("/site/media/cat.png")
+        );
+    }
+}
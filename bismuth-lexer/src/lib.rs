@@ -38,6 +38,15 @@ impl Lexer {
         Self::new(file)
     }
 
+    /// The line that a position is on, starting at 1
+    pub fn line_at(&self, position: usize) -> usize {
+        self.chars[..position.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1
+    }
+
     pub fn get_lines(&self) -> Vec<(usize, String)> {
         let mut curr_line = String::new();
         let mut lines: Vec<(usize, String)> = Vec::new();
@@ -72,7 +81,7 @@ impl Lexer {
         }
 
         self.position = pos;
-        return self.current();
+        self.current()
     }
 
     fn next(&mut self) -> Result<&char, LexerError> {
@@ -81,7 +90,7 @@ impl Lexer {
         }
 
         self.position += 1;
-        return self.current();
+        self.current()
     }
 
     fn next_line(&mut self) -> usize {
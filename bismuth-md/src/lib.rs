@@ -18,6 +18,13 @@ pub enum MarkdownFileError {
     NotDirectoryError(String),
 }
 
+/// Checks if a file is markdown by its extension, ie. `post.md` or `post.markdown`
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .map(|e| matches!(e.to_ascii_lowercase().to_str(), Some("md" | "markdown")))
+        .unwrap_or_default()
+}
+
 impl MarkdownFile {
     pub fn load_file(path: &PathBuf, rel: &Path) -> Result<Self, MarkdownFileError> {
         if !path.is_file() {
@@ -26,20 +33,15 @@ impl MarkdownFile {
             ));
         }
 
-        if let Some(s) = path.extension() {
-            let s = s.to_ascii_lowercase();
-            let s = s.to_str().expect("Should be string");
-
-            if matches!(s, "md" | "markdown") {
-                return Ok(MarkdownFile {
-                    path: rel.to_path_buf(),
-                    content: fs::read_to_string(path).expect("file should be there"),
-                });
-            }
+        if is_markdown(path) {
+            return Ok(MarkdownFile {
+                path: rel.to_path_buf(),
+                content: fs::read_to_string(path).expect("file should be there"),
+            });
         }
-        return Err(MarkdownFileError::NotMarkdownError(
+        Err(MarkdownFileError::NotMarkdownError(
             path.to_string_lossy().to_string(),
-        ));
+        ))
     }
 }
 
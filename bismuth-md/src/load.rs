@@ -51,7 +51,7 @@ mod test {
         let path = PathBuf::from(path).canonicalize().unwrap();
         let mut files =
             load_from_dir(&path, &PathBuf::from(&path).canonicalize().unwrap()).unwrap();
-        for mut file in &mut files {
+        for file in &mut files {
             let new_path = file
                 .path
                 .to_string_lossy()
@@ -18,6 +18,8 @@ pub struct CustomElm {
     pub values: HashMap<String, String>,
     pub body: Option<String>,
     pub template: Option<String>,
+    /// The line in the markdown file that the element starts on, 0 if it was not parsed from a file
+    pub line: usize,
 }
 
 impl CustomElm {
@@ -61,6 +63,7 @@ impl CustomElm {
             values,
             body,
             template: None,
+            line: 0,
         })
     }
 }
@@ -0,0 +1,56 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+const DATE_TIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%B %d, %Y", "%B %d %Y", "%d %B %Y"];
+
+/// Parses a date from the frontmatter
+/// Dates without a time will be at the start of the day
+/// Returns None if it is not a known format
+pub fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    let date = date.trim();
+
+    if let Ok(d) = DateTime::parse_from_rfc3339(date) {
+        return Some(d.naive_local());
+    }
+    for format in DATE_TIME_FORMATS {
+        if let Ok(d) = NaiveDateTime::parse_from_str(date, format) {
+            return Some(d);
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(d) = NaiveDate::parse_from_str(date, format) {
+            return d.and_hms_opt(0, 0, 0);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32, h: u32, min: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d)?.and_hms_opt(h, min, 0)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_date("2023-03-10"), date(2023, 3, 10, 0, 0));
+        assert_eq!(parse_date("2023/03/10"), date(2023, 3, 10, 0, 0));
+        assert_eq!(parse_date("2023-03-10 12:30"), date(2023, 3, 10, 12, 30));
+        assert_eq!(parse_date("2023-03-10T12:30:00"), date(2023, 3, 10, 12, 30));
+        assert_eq!(
+            parse_date("2023-03-10T12:30:00+02:00"),
+            date(2023, 3, 10, 12, 30)
+        );
+        assert_eq!(parse_date("March 10, 2023"), date(2023, 3, 10, 0, 0));
+        assert_eq!(parse_date("10 March 2023"), date(2023, 3, 10, 0, 0));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse_date("never"), None);
+        assert_eq!(parse_date("March 10"), None);
+        assert_eq!(parse_date(""), None);
+    }
+}
@@ -0,0 +1,140 @@
+/// The options after the ``` of a code block
+/// Ie ```` ```rust title="main.rs" {3-5} linenos ````
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Fence {
+    pub lang: String,
+    pub title: Option<String>,
+    /// The lines to emphasize, ie `3-5,7`
+    pub highlight: Option<String>,
+    pub linenos: bool,
+    /// Lines starting with `+`/`-` are shown as added/removed
+    /// Set with `diff`, or with a lang like `diff-rust`
+    pub diff: bool,
+}
+
+/// Splits on spaces that are not in quotes or `{}`
+fn split(info: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_braces = false;
+    for c in info.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '{') => in_braces = true,
+            (None, '}') => in_braces = false,
+            (None, c) if c.is_whitespace() && !in_braces => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+fn unquote(s: &str) -> String {
+    let s = s.trim();
+    for q in ['"', '\''] {
+        if s.len() >= 2 && s.starts_with(q) && s.ends_with(q) {
+            return s[1..s.len() - 1].to_string();
+        }
+    }
+    s.to_string()
+}
+
+impl Fence {
+    pub fn parse(info: &str) -> Self {
+        let mut fence = Fence::default();
+        for part in split(info) {
+            if part.starts_with('{') && part.ends_with('}') {
+                let ranges = part[1..part.len() - 1].replace(' ', "");
+                fence.highlight = Some(ranges);
+            } else if let Some((key, value)) = part.split_once('=') {
+                match key.trim().to_lowercase().as_str() {
+                    "title" => fence.title = Some(unquote(value)),
+                    "hl_lines" | "highlight" => fence.highlight = Some(unquote(value)),
+                    _ => {}
+                }
+            } else {
+                match part.to_lowercase().as_str() {
+                    "linenos" => fence.linenos = true,
+                    "diff" if !fence.lang.is_empty() => fence.diff = true,
+                    _ if fence.lang.is_empty() => fence.lang = part,
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(lang) = fence.lang.strip_prefix("diff-") {
+            fence.lang = lang.to_string();
+            fence.diff = true;
+        }
+        fence
+    }
+}
+
+/// Parses line ranges, ie `3-5,7` -> `[(3, 5), (7, 7)]`
+/// Anything that is not a number or a range is skipped
+pub fn parse_ranges(s: &str) -> Vec<(usize, usize)> {
+    s.split(',')
+        .filter_map(|range| {
+            let range = range.trim();
+            match range.split_once('-') {
+                Some((start, end)) => {
+                    let start = start.trim().parse::<usize>().ok()?;
+                    let end = end.trim().parse::<usize>().ok()?;
+                    Some((start.min(end), start.max(end)))
+                }
+                None => range.parse::<usize>().ok().map(|n| (n, n)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fence() {
+        assert_eq!(
+            Fence::parse(r#"rust title="src/main.rs" {3-5, 7} linenos"#),
+            Fence {
+                lang: String::from("rust"),
+                title: Some(String::from("src/main.rs")),
+                highlight: Some(String::from("3-5,7")),
+                linenos: true,
+                diff: false,
+            }
+        );
+        assert_eq!(Fence::parse("rs").lang, "rs");
+        assert_eq!(Fence::parse("").lang, "");
+        assert_eq!(
+            Fence::parse("title='a b.py' python").title,
+            Some(String::from("a b.py"))
+        );
+
+        let diff = Fence::parse("diff-rust");
+        assert_eq!((diff.lang.as_str(), diff.diff), ("rust", true));
+        let diff = Fence::parse("rust diff");
+        assert_eq!((diff.lang.as_str(), diff.diff), ("rust", true));
+        // a diff file, not diff mode
+        let diff = Fence::parse("diff");
+        assert_eq!((diff.lang.as_str(), diff.diff), ("diff", false));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_ranges("3-5,7"), [(3, 5), (7, 7)]);
+        assert_eq!(parse_ranges("5-3, x, 1"), [(3, 5), (1, 1)]);
+        assert!(parse_ranges("").is_empty());
+    }
+}
@@ -1,16 +1,36 @@
+use chrono::NaiveDateTime;
 use serde::Deserialize;
 use serde_yaml::{from_str, Error};
 use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
+use crate::date::parse_date;
+
 #[derive(Default, Deserialize, Debug, Clone)]
 pub struct FrontMatter {
     file_name: Option<String>,
     title: Option<String>,
     path: Option<String>,
+    /// Used in the url instead of the file name
+    slug: Option<String>,
+    /// Old urls of the page, these redirect to it
+    aliases: Option<Vec<String>>,
 
     kind: Option<String>,
 
+    draft: Option<bool>,
+    publish_date: Option<String>,
+    expiry_date: Option<String>,
+
+    tags: Option<Vec<String>>,
+    categories: Option<Vec<String>>,
+
+    series: Option<String>,
+    series_order: Option<i32>,
+
+    description: Option<String>,
+    image: Option<String>,
+
     values: Option<Vec<BTreeMap<String, String>>>,
 }
 
@@ -67,10 +87,76 @@ impl FrontMatter {
         self.file_name.as_ref()
     }
 
+    pub fn get_slug(&self) -> Option<&String> {
+        self.slug.as_ref()
+    }
+
+    pub fn get_aliases(&self) -> Option<&Vec<String>> {
+        self.aliases.as_ref()
+    }
+
     pub fn get_title(&self) -> Option<&String> {
         self.title.as_ref()
     }
 
+    pub fn is_draft(&self) -> bool {
+        self.draft.unwrap_or_default()
+    }
+
+    /// Returns None if there is no publish date, or if it could not be parsed
+    pub fn get_publish_date(&self) -> Option<NaiveDateTime> {
+        parse_date(self.publish_date.as_ref()?)
+    }
+
+    /// Returns None if there is no expiry date, or if it could not be parsed
+    pub fn get_expiry_date(&self) -> Option<NaiveDateTime> {
+        parse_date(self.expiry_date.as_ref()?)
+    }
+
+    pub fn get_tags(&self) -> Option<&Vec<String>> {
+        self.tags.as_ref()
+    }
+
+    pub fn get_categories(&self) -> Option<&Vec<String>> {
+        self.categories.as_ref()
+    }
+
+    pub fn get_series(&self) -> Option<&String> {
+        self.series.as_ref()
+    }
+
+    pub fn get_series_order(&self) -> Option<i32> {
+        self.series_order
+    }
+
+    pub fn get_description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+
+    pub fn get_image(&self) -> Option<&String> {
+        self.image.as_ref()
+    }
+
+    /// Checks if the page should be built at `now`
+    /// Expired pages are never built
+    /// Drafts and pages with a publish date after `now` are only built if `drafts`/`future` is set
+    pub fn is_published(&self, now: &NaiveDateTime, drafts: bool, future: bool) -> bool {
+        if self.is_draft() && !drafts {
+            return false;
+        }
+        if let Some(date) = self.get_publish_date() {
+            if &date > now && !future {
+                return false;
+            }
+        }
+        if let Some(date) = self.get_expiry_date() {
+            if &date <= now {
+                return false;
+            }
+        }
+        true
+    }
+
     fn fill_defaults(&mut self) {
         if self.kind.is_none() {
             self.kind = Some(String::from("default"));
@@ -98,6 +184,14 @@ impl FrontMatter {
             }
         }
 
+        if let Some(s) = updated_fm.slug {
+            self.slug = Some(s);
+        }
+
+        if let Some(a) = updated_fm.aliases {
+            self.aliases = Some(a);
+        }
+
         if let Some(n) = updated_fm.title {
             let n = Some(n);
             if self.title != n {
@@ -112,6 +206,42 @@ impl FrontMatter {
             }
         }
 
+        if let Some(d) = updated_fm.draft {
+            self.draft = Some(d);
+        }
+
+        if let Some(d) = updated_fm.publish_date {
+            self.publish_date = Some(d);
+        }
+
+        if let Some(d) = updated_fm.expiry_date {
+            self.expiry_date = Some(d);
+        }
+
+        if let Some(t) = updated_fm.tags {
+            self.tags = Some(t);
+        }
+
+        if let Some(c) = updated_fm.categories {
+            self.categories = Some(c);
+        }
+
+        if let Some(s) = updated_fm.series {
+            self.series = Some(s);
+        }
+
+        if let Some(o) = updated_fm.series_order {
+            self.series_order = Some(o);
+        }
+
+        if let Some(d) = updated_fm.description {
+            self.description = Some(d);
+        }
+
+        if let Some(i) = updated_fm.image {
+            self.image = Some(i);
+        }
+
         if self.values != updated_fm.values {
             self.values = updated_fm.values;
         }
@@ -187,6 +317,16 @@ mod tests_utils {
         "/path/test".to_string()
     );
 
+    snapshot!(
+        test_load_3,
+        "
+        title: this is a title
+        draft: true
+        publish_date: 2023-03-10
+        expiry_date: 2024-03-10 12:00
+        "
+    );
+
     snapshot!(
         test_load_2,
         "
@@ -198,4 +338,68 @@ mod tests_utils {
         path,
         "/path/test".to_string()
     );
+
+    snapshot!(
+        test_load_4,
+        "
+        title: this is a title
+        tags:
+            - rust
+            - Web Dev
+        categories: [programming]
+        "
+    );
+
+    snapshot!(
+        test_load_5,
+        "
+        title: Part two
+        series: Making a blog
+        series_order: 2
+        "
+    );
+
+    snapshot!(
+        test_load_6,
+        "
+        title: A post
+        slug: a-post
+        "
+    );
+
+    snapshot!(
+        test_load_7,
+        "
+        title: Moved
+        aliases:
+            - /old/post.html
+            - /2023/post/
+        "
+    );
+
+    fn published(update: &str, drafts: bool, future: bool) -> bool {
+        let mut fm = FrontMatter::default();
+        fm.update_from_str(update).unwrap();
+        let now = parse_date("2023-06-01").unwrap();
+        fm.is_published(&now, drafts, future)
+    }
+
+    #[test]
+    fn is_published() {
+        assert!(published("title: test", false, false));
+
+        assert!(!published("draft: true", false, false));
+        assert!(published("draft: true", true, false));
+        assert!(published("draft: false", false, false));
+
+        assert!(!published("publish_date: 2023-07-01", false, false));
+        assert!(published("publish_date: 2023-07-01", false, true));
+        assert!(published("publish_date: 2023-05-01", false, false));
+
+        assert!(!published("expiry_date: 2023-05-01", true, true));
+        assert!(published("expiry_date: 2023-07-01", false, false));
+
+        // unknown dates are ignored
+        assert!(published("publish_date: tomorrow", false, false));
+    }
 }
@@ -0,0 +1,81 @@
+use crate::{
+    slug::slugify,
+    tree::{Ast, Element, Kind},
+};
+
+/// Gets the plain text of an element and everything inside of it
+/// Ie `## Hello **World**` -> `Hello World`
+pub fn element_text(element: &Element) -> String {
+    let mut text = match element.kind {
+        Kind::CustomElement(_) => return String::new(),
+        _ => element.text.clone().unwrap_or_default(),
+    };
+    for elm in &element.elements {
+        text.push_str(&element_text(elm));
+    }
+    text
+}
+
+/// Gives every header a unique `id` attr, made from its text
+/// Headers with the same text get `-1`, `-2`... added to the end
+pub fn assign_ids(ast: &mut Ast) {
+    let mut used: Vec<String> = vec![];
+    for element in &mut ast.elements {
+        assign_id(element, &mut used);
+    }
+}
+
+fn assign_id(element: &mut Element, used: &mut Vec<String>) {
+    if element.kind == Kind::Header {
+        let mut base = slugify(&element_text(element));
+        if base.is_empty() {
+            base = String::from("section");
+        }
+
+        let mut id = base.clone();
+        let mut count = 1;
+        while used.contains(&id) {
+            id = format!("{base}-{count}");
+            count += 1;
+        }
+        element.add_attr("id", &id);
+        used.push(id);
+        return;
+    }
+
+    for elm in &mut element.elements {
+        assign_id(elm, used);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+
+    fn ids(content: &str) -> Vec<String> {
+        let mut parser = Parser::new_test("/test/test.md", content);
+        parser.parse().unwrap();
+        parser
+            .ast
+            .elements
+            .iter()
+            .filter_map(|e| e.get_attr("id").ok().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn unique_ids() {
+        assert_eq!(
+            ids("# Intro\n## Intro\n## Intro\n### Intro 1"),
+            ["intro", "intro-1", "intro-2", "intro-1-1"]
+        );
+    }
+
+    #[test]
+    fn markup_ids() {
+        assert_eq!(
+            ids("# Hello **World**\n## Use `<div>` [here](https://example.com)\n# !!!"),
+            ["hello-world", "use-div-here", "section"]
+        );
+    }
+}
@@ -2,13 +2,19 @@ use bismuth_lexer::{
     token::{Token, TokenType},
     Lexer,
 };
+use chrono::NaiveDateTime;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 pub mod custom;
+pub mod date;
 pub mod error;
+pub mod fence;
 mod frontmatter;
+pub mod heading;
+pub mod slug;
 pub mod tree;
+pub mod url;
 use crate::{
     error::ParseError,
     frontmatter::FrontMatter,
@@ -19,6 +25,8 @@ use crate::{
 pub struct Metadata {
     pub absolute_path: PathBuf,
     pub frontmatter: FrontMatter,
+    /// When the file was last modified, this is only set for files loaded from the disk
+    pub modified: Option<NaiveDateTime>,
 }
 
 impl Metadata {
@@ -26,6 +34,7 @@ impl Metadata {
         Metadata {
             absolute_path: path.to_path_buf(),
             frontmatter: FrontMatter::new(path),
+            modified: None,
         }
     }
 }
@@ -107,6 +116,17 @@ impl Parser {
         (token.end - token.start) + 1
     }
 
+    /// The line that the current token is on, starting at 1
+    fn current_line(&self) -> usize {
+        let position = self
+            .lexer
+            .tokens
+            .get(self.index)
+            .map(|t| t.start)
+            .unwrap_or_default();
+        self.lexer.line_at(position)
+    }
+
     fn current_token_len(&self) -> Result<usize, ParseError> {
         Ok(self.token_len(self.current_token()?))
     }
@@ -353,6 +373,7 @@ impl Parser {
     }
 
     fn make_custom(&mut self) -> Result<Element, ParseError> {
+        let line = self.current_line();
         // advance past %{{
         self.advance_n_token(2)?;
 
@@ -385,8 +406,9 @@ impl Parser {
         self.advance_n_token(inside_tokens.len() + 1)?;
 
         // makes the custom element
-        let c =
+        let mut c =
             custom::CustomElm::from_string(&inside_str).map_err(ParseError::CustomElementError)?;
+        c.line = line;
         Ok(Element::new(Kind::CustomElement(c)))
     }
 
@@ -435,8 +457,21 @@ impl Parser {
                 .collect::<String>();
 
             // make the blockcode element
+            let fence = fence::Fence::parse(&lang);
             let mut elm = Element::new(Kind::BlockCode);
-            elm.add_attr("lang", &lang);
+            elm.add_attr("lang", &fence.lang);
+            if let Some(title) = fence.title {
+                elm.add_attr("title", &title);
+            }
+            if let Some(highlight) = fence.highlight {
+                elm.add_attr("highlight", &highlight);
+            }
+            if fence.linenos {
+                elm.add_attr("linenos", &true);
+            }
+            if fence.diff {
+                elm.add_attr("diff", &true);
+            }
 
             // append the text inside the blockcode
             elm.text = Some(code);
@@ -476,7 +511,7 @@ impl Parser {
             inside
         } else {
             self.advance_token()?;
-            let pattern = vec![kind].repeat(len);
+            let pattern = [kind].repeat(len);
             let pat_start = self.peek_till_pattern(&pattern)?;
             self.peek_till(pat_start - self.index)?
         };
@@ -567,11 +602,17 @@ impl Parser {
 
     // Somewhat same as *
     fn handle_bracket(&mut self) -> ParseReturn {
+        if let Some(elm) = self.get_wiki_link()? {
+            self.append_element(elm);
+            return Ok(());
+        }
+        let line = self.current_line();
         match self.get_url() {
             Ok((text, url)) => {
                 let mut elm = Element::new(Kind::Link);
                 elm.text = Some(text);
                 elm.add_attr("link", &url);
+                elm.add_attr("line", &line);
                 self.append_element(elm);
             }
             Err(ParseError::Peek(_)) => {
@@ -601,11 +642,13 @@ impl Parser {
         }
 
         self.advance_token()?;
+        let line = self.current_line();
         match self.get_url() {
             Ok((text, url)) => {
                 let mut elm = Element::new(Kind::FilePrev);
                 elm.text = Some(text);
                 elm.add_attr("link", &url);
+                elm.add_attr("line", &line);
                 self.append_element(elm);
             }
             Err(ParseError::Peek(_)) => {
@@ -640,6 +683,46 @@ impl Parser {
         Ok((text_s, link_s))
     }
 
+    /// `[[page]]` or `[[page|text]]`
+    /// The link is found later, from all the pages. Returns `None` if this is not a wiki link
+    fn get_wiki_link(&mut self) -> Result<Option<Element>, ParseError> {
+        // the lexer puts `[[` into one token
+        if self.current_token_chars()?.len() != 2 {
+            return Ok(None);
+        }
+        let inside = self
+            .lexer
+            .tokens
+            .iter()
+            .skip(self.index + 1)
+            .take_while(|t| t.kind != TokenType::BracketRight && t.kind != TokenType::EndOfLine)
+            .collect::<Vec<&Token>>();
+        let end = self.index + 1 + inside.len();
+        let closed = self
+            .peek_at(end)
+            .map(|t| t.kind == TokenType::BracketRight && t.text.len() == 2)
+            .unwrap_or_default();
+        let inside = inside
+            .iter()
+            .map(|t| t.text.iter().collect::<String>())
+            .collect::<String>();
+        if !closed || inside.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let (page, text) = match inside.split_once('|') {
+            Some((page, text)) => (page.trim(), text.trim()),
+            None => (inside.trim(), inside.trim()),
+        };
+        let mut elm = Element::new(Kind::Link);
+        elm.text = Some(text.to_string());
+        elm.add_attr("wiki", &page);
+        elm.add_attr("line", &self.current_line());
+        // end on the `]]`
+        self.advance_n_token(end - self.index)?;
+        Ok(Some(elm))
+    }
+
     fn handle_frontmatter(&mut self) -> ParseReturn {
         let mut inside = self.peek_till_kind(&TokenType::FrontmatterEnd).unwrap();
         inside.remove(0);
@@ -713,6 +796,8 @@ impl Parser {
             self.ast.elements.push(elm);
         }
         self.current_element = None;
+
+        heading::assign_ids(&mut self.ast);
         Ok(())
     }
 }
@@ -745,7 +830,15 @@ fn render_element(element: &Element, level: usize) -> String {
         "Element{{\n{ts}{t}Kind: {:#?},\n{ts}{t}Text: {:?},\n{ts}{t}Attrs: {},\n{ts}{t}Elements: [",
         format_kind(&element.kind),
         element.text,
-        sort_hm(&element.attrs)
+        // where the element is in the file is not part of the element
+        sort_hm(
+            &element
+                .attrs
+                .iter()
+                .filter(|(k, _)| k.as_str() != "line")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        )
     );
     for elm in &element.elements {
         let inside_s = format!("\n{ts}{ts}{t}{},", render_element(elm, level + 1));
@@ -785,6 +878,63 @@ mod test_utils {
     #[derive(Debug, PartialEq)]
     struct TestError {}
 
+    #[test]
+    fn custom_line() {
+        let lexer = init_lexer("---\ntitle: a\n---\ntext\n\n%{{\nname: test\n}}\n");
+        let mut parser = Parser::new(lexer);
+        parser.parse().unwrap();
+        let line = parser.ast.elements.iter().find_map(|e| match &e.kind {
+            Kind::CustomElement(c) => Some(c.line),
+            _ => None,
+        });
+        assert_eq!(line, Some(6));
+    }
+
+    #[test]
+    fn wiki_link() {
+        let lexer =
+            init_lexer("text\nsee [[another post|the post]] and [[About]]\n[x](/x.html) [[]]");
+        let mut parser = Parser::new(lexer);
+        parser.parse().unwrap();
+        let links = parser
+            .ast
+            .elements
+            .iter()
+            .flat_map(|e| [e].into_iter().chain(&e.elements))
+            .filter(|e| e.kind == Kind::Link)
+            .map(|e| {
+                (
+                    e.get_text().unwrap().as_str(),
+                    e.get_attr("wiki").ok().map(String::as_str),
+                    e.get_attr("line").ok().map(String::as_str),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                ("the post", Some("another post"), Some("2")),
+                ("About", Some("About"), Some("2")),
+                ("x", None, Some("3")),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_code_fence() {
+        let lexer = init_lexer("```rust title=\"main.rs\" {2-3} linenos\nfn main() {}\n```");
+        let mut parser = Parser::new(lexer);
+        parser.parse().unwrap();
+        let code = &parser.ast.elements[0];
+        assert_eq!(code.kind, Kind::BlockCode);
+        let attr = |key: &str| code.get_attr(key).ok().cloned();
+        assert_eq!(attr("lang").as_deref(), Some("rust"));
+        assert_eq!(attr("title").as_deref(), Some("main.rs"));
+        assert_eq!(attr("highlight").as_deref(), Some("2-3"));
+        assert_eq!(attr("linenos").as_deref(), Some("true"));
+        assert_eq!(attr("diff"), None);
+    }
+
     #[test]
     fn advance_token_test() {
         let lexer = init_lexer("this is a test []");
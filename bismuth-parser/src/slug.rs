@@ -0,0 +1,28 @@
+/// Makes a string safe to use in a url or a html id
+/// Ie `Hello, World!` -> `hello-world`
+pub fn slugify(s: &str) -> String {
+    let mut slug = String::new();
+    for c in s.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  rust  "), "rust");
+        assert_eq!(slugify("C++ & Rust"), "c-rust");
+        assert_eq!(slugify("snake_case"), "snake_case");
+        assert_eq!(slugify("Ünïcode Tag"), "ünïcode-tag");
+        assert_eq!(slugify("---"), "");
+    }
+}
@@ -0,0 +1,584 @@
+use chrono::NaiveDateTime;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{
+    date::parse_date,
+    tree::{Element, Kind},
+    Parser,
+};
+
+/// How the urls in the site are made
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlMode {
+    /// From the base url, ie. `https://example.com/docs/blogs/post.html` or `/docs/blogs/post.html`
+    #[default]
+    Absolute,
+    /// Relative to the page they are on, ie. `../blogs/post.html`
+    /// The site then works in any dir, or when opened from the disk
+    Relative,
+}
+
+/// The `[permalinks]` section of `bismuth.toml`
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct PermalinkConfig {
+    /// Pages are written to `<path>/<slug>/index.html`, so their urls do not end in `.html`
+    pub pretty: bool,
+    /// Patterns for the pages in a dir, ie. `blogs = "/blog/{year}/{month}/{slug}/"`
+    pub dirs: BTreeMap<String, String>,
+    /// Patterns for the pages with a `kind`, these are used before the ones for the dirs
+    pub kinds: BTreeMap<String, String>,
+}
+
+/// Makes every url in the site, so they all have the base url
+/// This is made from the config once for each build, and passed to what makes urls
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Urls {
+    /// Without the `/` at the end, ie. `https://example.com/docs`, `/docs` or empty for the root
+    base: String,
+    mode: UrlMode,
+    permalinks: PermalinkConfig,
+}
+
+/// What the url of a page is made from
+struct Page<'a> {
+    /// The dir from the src dir, without the `/` at the end, ie. `/blogs` or empty
+    dir: String,
+    name: &'a str,
+    slug: Option<&'a str>,
+    kind: &'a str,
+    date: Option<NaiveDateTime>,
+}
+
+impl<'a> Page<'a> {
+    fn new(parser: &'a Parser) -> Self {
+        let frontmatter = &parser.metadata.frontmatter;
+        Page {
+            dir: frontmatter
+                .get_path()
+                .map(|p| p.replace('\\', "/").trim_end_matches('/').to_string())
+                .unwrap_or_default(),
+            name: frontmatter
+                .get_file_name()
+                .map(String::as_str)
+                .unwrap_or_default(),
+            slug: frontmatter.get_slug().map(String::as_str),
+            kind: frontmatter
+                .get_kind()
+                .map(String::as_str)
+                .unwrap_or_default(),
+            date: frontmatter
+                .get_value("date")
+                .and_then(|d| parse_date(d))
+                .or_else(|| frontmatter.get_publish_date()),
+        }
+    }
+}
+
+/// Urls with a scheme (ie. `https:`, `mailto:`) or that start with `//` go to other sites
+pub fn is_external(url: &str) -> bool {
+    if url.starts_with("//") {
+        return true;
+    }
+    // a scheme is a letter, then letters, digits, `+`, `.` or `-`
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+        }
+        None => false,
+    }
+}
+
+/// Where the page would be without pretty urls or permalinks, relative links in the page are from here
+/// Ie `/blogs/post.md` -> `/blogs/post.html`
+pub fn source(parser: &Parser) -> String {
+    let page = Page::new(parser);
+    format!("{}/{}.html", page.dir, page.name)
+}
+
+/// The html file a url is written to, from the root of the site
+/// Ie `/blogs/post.html` -> `/blogs/post.html`, `/blogs/post/` -> `/blogs/post/index.html`
+pub fn html_file(url: &str) -> String {
+    match url.ends_with('/') || url.is_empty() {
+        true => format!("{url}index.html"),
+        false => url.to_string(),
+    }
+}
+
+/// The dir a url is in, urls that end with `/` are their own dir
+/// Ie `/blogs/post.html` -> `/blogs/`, `/blogs/post/` -> `/blogs/post/`
+pub fn dir(url: &str) -> &str {
+    &url[..url.rfind('/').map(|i| i + 1).unwrap_or_default()]
+}
+
+/// Makes `to` relative to the page at `from`, both are urls from the root
+/// Ie `/blogs/post.html` and `/about.html` -> `../about.html`
+pub fn relative(from: &str, to: &str) -> String {
+    let from_dirs = from
+        .trim_start_matches('/')
+        .split('/')
+        .collect::<Vec<&str>>();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts = to.trim_start_matches('/').split('/').collect::<Vec<&str>>();
+    let to_dirs = &to_parts[..to_parts.len() - 1];
+
+    let common = from_dirs
+        .iter()
+        .zip(to_dirs)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts = vec![".."; from_dirs.len() - common];
+    parts.extend_from_slice(&to_parts[common..]);
+    match parts.join("/") {
+        // the dir the page is in
+        url if url.is_empty() => String::from("./"),
+        url => url,
+    }
+}
+
+fn rebase_links(elements: &mut [Element], page: &str) {
+    for element in elements {
+        if let (Kind::Link, Some(link)) = (&element.kind, element.attrs.get_mut("link")) {
+            let url = link.trim();
+            if !url.is_empty() && !url.starts_with(['/', '#']) && !is_external(url) {
+                *link = resolve(page, url);
+            }
+        }
+        rebase_links(&mut element.elements, page);
+    }
+}
+
+/// Makes the url of a local file (ie. a image) from the root of the site
+/// These are always from the project dir, and are copied to the same place in the output
+/// Ie `assets/cat.png` and `/assets/cat.png` -> `/assets/cat.png`
+pub fn asset(path: &str) -> String {
+    resolve("/", path)
+}
+
+/// Makes a url relative to the page into one from the root, and removes `.` and `..`
+/// Ie `/blogs/post.html` and `../about.html` -> `/about.html`
+pub fn resolve(page: &str, path: &str) -> String {
+    let mut parts = match path.starts_with('/') {
+        true => vec![],
+        false => {
+            let mut parts = page
+                .split('/')
+                .filter(|p| !p.is_empty())
+                .collect::<Vec<&str>>();
+            parts.pop();
+            parts
+        }
+    };
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    let mut url = format!("/{}", parts.join("/"));
+    if path.ends_with('/') && url != "/" {
+        url.push('/');
+    }
+    url
+}
+
+impl Urls {
+    /// `base_url` can be a full url (ie. `https://example.com/docs/`) or only the path (ie. `/docs/`)
+    pub fn new(base_url: &str, mode: UrlMode) -> Self {
+        let base = base_url.trim().trim_end_matches('/');
+        let base = match base.is_empty() || is_external(base) || base.starts_with('/') {
+            true => base.to_string(),
+            false => format!("/{base}"),
+        };
+        Urls {
+            base,
+            mode,
+            permalinks: PermalinkConfig::default(),
+        }
+    }
+
+    pub fn with_permalinks(mut self, permalinks: PermalinkConfig) -> Self {
+        self.permalinks = permalinks;
+        self
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn mode(&self) -> UrlMode {
+        self.mode
+    }
+
+    /// The url of the html file for a page, from the root of the site
+    /// Ie `/blogs/post.md` -> `/blogs/post.html`, or `/blogs/post/` with pretty urls
+    /// If the permalink of the page can not be made, the default url is used (see [`Urls::check`])
+    pub fn path(&self, parser: &Parser) -> String {
+        let page = Page::new(parser);
+        self.permalink(&page)
+            .unwrap_or_else(|_| self.default_url(&page))
+    }
+
+    /// Checks that the permalink pattern for a page can be made, ie. it has a date for `{year}`
+    pub fn check(&self, parser: &Parser) -> Result<(), String> {
+        self.permalink(&Page::new(parser)).map(|_| ())
+    }
+
+    /// The url of a page that is made by the build, from the path of its markdown file
+    /// Ie `/tags/rust.md` -> `/tags/rust.html`
+    pub fn file(&self, path: &str, kind: &str) -> String {
+        let path = Path::new(path);
+        let page = Page {
+            dir: path
+                .parent()
+                .map(|p| p.to_string_lossy().trim_end_matches('/').to_string())
+                .unwrap_or_default(),
+            name: path
+                .file_stem()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default(),
+            slug: None,
+            kind,
+            date: None,
+        };
+        self.permalink(&page)
+            .unwrap_or_else(|_| self.default_url(&page))
+    }
+
+    /// Makes the relative links in a page from the root of the site, if the page is not written to the
+    /// dir of its markdown file (ie. with pretty urls), so they still go to the same place
+    /// Images are not changed, they are always from the project dir
+    /// This should be done before running the customs, the links they make are already right
+    pub fn rebase(&self, parser: &mut Parser) {
+        let source = source(parser);
+        if dir(&self.path(parser)) != dir(&source) {
+            rebase_links(&mut parser.ast.elements, &source);
+        }
+    }
+
+    /// The url for `path` (from the root of the site) on the page at `from`
+    /// Urls to other sites, and urls that are already relative are not changed
+    pub fn url(&self, path: &str, from: &str) -> String {
+        if !path.starts_with('/') || is_external(path) {
+            return path.to_string();
+        }
+        match self.mode {
+            UrlMode::Absolute => format!("{}{path}", self.base),
+            // so pretty urls still work when the site is opened from the disk
+            UrlMode::Relative => match path.find(['#', '?']) {
+                Some(i) if path[..i].ends_with('/') => {
+                    relative(from, &format!("{}index.html{}", &path[..i], &path[i..]))
+                }
+                None if path.ends_with('/') => relative(from, &format!("{path}index.html")),
+                _ => relative(from, path),
+            },
+        }
+    }
+
+    /// The root of the site on the page at `from`, without the `/` at the end
+    /// Ie `{root}/assets/css/base.css`
+    pub fn root(&self, from: &str) -> String {
+        match self.mode {
+            UrlMode::Absolute => self.base.clone(),
+            UrlMode::Relative => relative(from, "/").trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The path of the base url, without the domain, ie. `https://example.com/docs` -> `/docs`
+    pub fn base_path(&self) -> &str {
+        match self.base.split_once("//") {
+            Some((_, host)) if is_external(&self.base) => {
+                host.find('/').map(|i| &host[i..]).unwrap_or_default()
+            }
+            _ => &self.base,
+        }
+    }
+
+    /// The full url, with the domain, for `path` (from the root of the site)
+    /// This is only known if the base url has the domain
+    pub fn full(&self, path: &str) -> Option<String> {
+        is_external(&self.base).then(|| format!("{}{path}", self.base))
+    }
+
+    /// `{path}/{slug}.html`, or `{path}/{slug}/` with pretty urls
+    /// The slug defaults to the file name
+    fn default_url(&self, page: &Page) -> String {
+        let slug = page.slug.unwrap_or(page.name);
+        match self.permalinks.pretty {
+            // so the index of a dir is still at the dir
+            true if slug == "index" => format!("{}/", page.dir),
+            true => format!("{}/{slug}/", page.dir),
+            false => format!("{}/{slug}.html", page.dir),
+        }
+    }
+
+    /// The pattern for the kind of the page, then for the dir it is in
+    /// Index pages are not changed by the pattern of their dir, so the dir still has a page
+    fn pattern(&self, page: &Page) -> Option<&String> {
+        self.permalinks.kinds.get(page.kind).or_else(|| {
+            if page.name == "index" {
+                return None;
+            }
+            self.permalinks
+                .dirs
+                .iter()
+                .find(|(dir, _)| dir.trim_matches('/') == page.dir.trim_start_matches('/'))
+                .map(|(_, pattern)| pattern)
+        })
+    }
+
+    /// Makes the url of a page from its pattern, ie `/blog/{year}/{month}/{slug}/` -> `/blog/2023/03/post/`
+    /// Patterns that end with `/` are written to the `index.html` in that dir, others get `.html` added
+    fn permalink(&self, page: &Page) -> Result<String, String> {
+        let pattern = match self.pattern(page) {
+            Some(pattern) => pattern,
+            None => return Ok(self.default_url(page)),
+        };
+        let placeholder = Regex::new(r"\{(\w+)\}").expect("Should be valid regex");
+        let mut error = None;
+        let url = placeholder.replace_all(pattern, |c: &Captures| {
+            let value = match &c[1] {
+                "path" => Some(page.dir.clone()),
+                "file_name" => Some(page.name.to_string()),
+                "slug" => Some(page.slug.unwrap_or(page.name).to_string()),
+                "kind" => Some(page.kind.to_string()),
+                "year" => page.date.map(|d| d.format("%Y").to_string()),
+                "month" => page.date.map(|d| d.format("%m").to_string()),
+                "day" => page.date.map(|d| d.format("%d").to_string()),
+                name => {
+                    error = Some(format!(
+                        "`{{{name}}}` in the permalink `{pattern}` is not known"
+                    ));
+                    Some(String::new())
+                }
+            };
+            value.unwrap_or_else(|| {
+                error.get_or_insert(format!(
+                    "`{}` in the permalink `{pattern}` needs a `date` value or a `publish_date`",
+                    &c[0]
+                ));
+                String::new()
+            })
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        let mut url = format!(
+            "/{}",
+            url.split('/')
+                .filter(|p| !p.is_empty())
+                .collect::<Vec<&str>>()
+                .join("/")
+        );
+        if pattern.ends_with('/') {
+            if url != "/" {
+                url.push('/');
+            }
+        } else if !url.ends_with(".html") {
+            url.push_str(".html");
+        }
+        Ok(url)
+    }
+
+    /// Removes the base url from a url, so it is from the root of the site
+    /// Ie `/docs/blogs/post.html` -> `/blogs/post.html`
+    pub fn strip(&self, url: &str) -> Option<String> {
+        if self.base.is_empty() {
+            return None;
+        }
+        let rest = url.strip_prefix(&self.base)?;
+        match rest.chars().next() {
+            None => Some(String::from("/")),
+            Some('/') => Some(rest.to_string()),
+            Some('#' | '?') => Some(format!("/{rest}")),
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_url() {
+        assert_eq!(relative("/blogs/post.html", "/about.html"), "../about.html");
+        assert_eq!(
+            relative("/index.html", "/blogs/post.html"),
+            "blogs/post.html"
+        );
+        assert_eq!(
+            relative("/blogs/a.html", "/blogs/b.html#intro"),
+            "b.html#intro"
+        );
+        assert_eq!(relative("/a/b/c.html", "/a/d/e.html"), "../d/e.html");
+        assert_eq!(relative("/index.html", "/"), "./");
+    }
+
+    #[test]
+    fn resolve_url() {
+        assert_eq!(resolve("/blogs/post.html", "../about.html"), "/about.html");
+        assert_eq!(
+            resolve("/blogs/post.html", "other.html"),
+            "/blogs/other.html"
+        );
+        assert_eq!(resolve("/index.html", "/assets/a.png"), "/assets/a.png");
+        assert_eq!(resolve("/index.html", "./blogs/"), "/blogs/");
+    }
+
+    #[test]
+    fn absolute() {
+        let urls = Urls::new("https://example.com/docs/", UrlMode::Absolute);
+        assert_eq!(urls.base(), "https://example.com/docs");
+        assert_eq!(
+            urls.url("/blogs/post.html", "/index.html"),
+            "https://example.com/docs/blogs/post.html"
+        );
+        assert_eq!(urls.url("post.html", "/index.html"), "post.html");
+        assert_eq!(
+            urls.url("https://other.com/", "/index.html"),
+            "https://other.com/"
+        );
+        assert_eq!(urls.root("/blogs/post.html"), "https://example.com/docs");
+        assert_eq!(
+            urls.full("/index.html"),
+            Some(String::from("https://example.com/docs/index.html"))
+        );
+        assert_eq!(
+            urls.strip("https://example.com/docs/a.html#b"),
+            Some(String::from("/a.html#b"))
+        );
+        assert_eq!(
+            urls.strip("https://example.com/docs"),
+            Some(String::from("/"))
+        );
+        assert_eq!(urls.strip("https://example.com/docsy/a.html"), None);
+        assert_eq!(urls.base_path(), "/docs");
+
+        let urls = Urls::new("docs", UrlMode::Absolute);
+        assert_eq!(urls.url("/a.html", "/index.html"), "/docs/a.html");
+        assert_eq!(urls.full("/a.html"), None);
+
+        let urls = Urls::default();
+        assert_eq!(urls.url("/a.html", "/b/c.html"), "/a.html");
+        assert_eq!(urls.root("/b/c.html"), "");
+        assert_eq!(urls.strip("/a.html"), None);
+    }
+
+    fn parse(path: &str, content: &str) -> Parser {
+        let mut parser = Parser::new_test(path, content);
+        parser.parse().unwrap();
+        parser
+    }
+
+    #[test]
+    fn permalinks() {
+        let urls = Urls::default().with_permalinks(PermalinkConfig {
+            pretty: true,
+            dirs: [(
+                String::from("/blogs/"),
+                String::from("/blog/{year}/{month}/{slug}/"),
+            )]
+            .into(),
+            kinds: [(String::from("note"), String::from("/notes/{file_name}"))].into(),
+        });
+        let permalink =
+            |path: &str, content: &str| urls.permalink(&Page::new(&parse(path, content)));
+
+        assert_eq!(
+            permalink(
+                "/blogs/post.md",
+                "---\nslug: hello-world\nvalues:\n    - date: 2023-03-10\n---\n"
+            ),
+            Ok(String::from("/blog/2023/03/hello-world/"))
+        );
+        assert_eq!(
+            permalink("/blogs/post.md", "---\npublish_date: 2024-01-02\n---\n"),
+            Ok(String::from("/blog/2024/01/post/"))
+        );
+        assert_eq!(
+            permalink("/blogs/index.md", "# Blogs\n"),
+            Ok(String::from("/blogs/"))
+        );
+        assert_eq!(
+            permalink("/blogs/nested/post.md", "# Post\n"),
+            Ok(String::from("/blogs/nested/post/"))
+        );
+        assert_eq!(
+            permalink("/blogs/post.md", "---\nkind: note\n---\n"),
+            Ok(String::from("/notes/post.html"))
+        );
+        assert_eq!(
+            permalink("/blogs/post.md", "# Post\n"),
+            Err(String::from(
+                "`{year}` in the permalink `/blog/{year}/{month}/{slug}/` needs a `date` value or a `publish_date`"
+            ))
+        );
+        assert_eq!(
+            permalink("/a.md", "---\nkind: note\npath: /\n---\n").map(|_| ()),
+            Ok(())
+        );
+
+        let urls = Urls::default();
+        assert_eq!(
+            urls.permalink(&Page::new(&parse("/blogs/post.md", "---\nslug: hi\n---\n"))),
+            Ok(String::from("/blogs/hi.html"))
+        );
+    }
+
+    #[test]
+    fn rebase_relative_links() {
+        let mut parser = parse(
+            "/blogs/post.md",
+            "[a](other.html) [b](/about.html) [c](#top) ![d](assets/d.png)\n",
+        );
+        fn links(elements: &[Element], found: &mut Vec<String>) {
+            for element in elements {
+                found.extend(element.get_attr("link").ok().cloned());
+                links(&element.elements, found);
+            }
+        }
+        let page = source(&parser);
+        rebase_links(&mut parser.ast.elements, &page);
+        let mut found = vec![];
+        links(&parser.ast.elements, &mut found);
+        assert_eq!(
+            found,
+            ["/blogs/other.html", "/about.html", "#top", "assets/d.png"]
+        );
+        assert_eq!(dir("/blogs/post/"), "/blogs/post/");
+        assert_eq!(dir("/blogs/post.html"), "/blogs/");
+        assert_eq!(html_file("/blogs/post/"), "/blogs/post/index.html");
+        assert_eq!(html_file("/blogs/post.html"), "/blogs/post.html");
+        assert_eq!(asset("assets/d.png"), "/assets/d.png");
+        assert_eq!(asset("./assets/d.png"), asset("/assets/d.png"));
+    }
+
+    #[test]
+    fn relative_mode() {
+        let urls = Urls::new("https://example.com/docs/", UrlMode::Relative);
+        assert_eq!(urls.url("/about.html", "/blogs/post.html"), "../about.html");
+        assert_eq!(urls.root("/blogs/post.html"), "..");
+        assert_eq!(urls.root("/index.html"), ".");
+        assert_eq!(
+            urls.url("/blogs/post/#intro", "/about/"),
+            "../blogs/post/index.html#intro"
+        );
+        assert_eq!(urls.url("/", "/about/"), "../index.html");
+        assert_eq!(
+            urls.full("/about.html"),
+            Some(String::from("https://example.com/docs/about.html"))
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! Helpers for the tests of the other crates, this is only a dev-dependency
+use std::fs;
+use tempfile::TempDir;
+
+/// Makes a project dir with `files` in it, it is removed when it is dropped
+pub fn project(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (file, content) in files {
+        let path = dir.path().join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}